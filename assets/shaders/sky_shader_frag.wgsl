@@ -10,7 +10,52 @@
 
 
 @group(2) @binding(0) var<uniform> time: vec4<f32>;
+@group(2) @binding(1) var<uniform> tint: vec4<f32>;
+// Rotation of the sun pivot. The sun is on its local x axis and the stars turn with it.
+@group(2) @binding(2) var<uniform> rotation: mat4x4<f32>;
+@group(2) @binding(3) var<uniform> moon: vec4<f32>;
 
+// Angular radius of the moon, in radians.
+const MOON_RADIUS: f32 = 0.04;
+const MOON_COLOR: vec3<f32> = vec3(2.0, 2.0, 1.8);
+// Cells per unit of direction in which at most one star is placed.
+const STAR_DENSITY: f32 = 150.0;
+// Fraction of cells without a star.
+const STAR_THRESHOLD: f32 = 0.985;
+
+
+fn hash(p: vec3<f32>) -> f32 {
+    var q = fract(p * 0.3183099 + vec3(0.1, 0.2, 0.3));
+    q *= 17.0;
+    return fract(q.x * q.y * q.z * (q.x + q.y + q.z));
+}
+
+fn stars(direction: vec3<f32>) -> f32 {
+    let local = (transpose(rotation) * vec4(direction, 0.0)).xyz;
+    let scaled = local * STAR_DENSITY;
+    let cell = floor(scaled);
+    if (hash(cell) < STAR_THRESHOLD) {
+        return 0.0;
+    }
+    let center = vec3(hash(cell + 11.0), hash(cell + 23.0), hash(cell + 37.0)) * 0.6 + 0.2;
+    let brightness = hash(cell + 5.0);
+    return smoothstep(0.25, 0.0, length(fract(scaled) - center)) * brightness;
+}
+
+fn moon_disc(direction: vec3<f32>) -> vec3<f32> {
+    let center = normalize(moon.xyz);
+    let cos_angle = dot(direction, center);
+    if (cos_angle < cos(MOON_RADIUS)) {
+        return vec3(0.0);
+    }
+    // The point on the moon's surface that is seen in this direction.
+    let offset = (direction - center * cos_angle) / sin(MOON_RADIUS);
+    let normal = offset - center * sqrt(max(1.0 - dot(offset, offset), 0.0));
+    // The side that faces the sun is lit, which gives the moon its phase.
+    let sun = rotation[0].xyz;
+    let lit = smoothstep(-0.05, 0.05, dot(normal, sun));
+    return MOON_COLOR * (0.03 + lit);
+}
 
 @fragment
 fn fragment(
@@ -23,9 +68,15 @@ fn fragment(
     var color = mix(color_night, color_day * 30.0, smoothstep(0.0, 0.5, time.x));
     var sunset = vec3(0.4, 0.2, 0.0);
     color = mix(color, sunset, theta * 0.2 * (1.0 - time.x));
+
+    let direction = normalize(vertex.world_position.xyz);
+    let night = 1.0 - smoothstep(0.0, 0.3, time.x);
+    color += vec3(stars(direction)) * night * 3.0;
+    color += moon_disc(direction) * mix(0.2, 1.0, night);
+
     if (theta > 1.58) {
         color = vec3(0.021, 0.119, 0.023) * 0.0;
     }
-    return vec4(color, 1.0);
+    return vec4(color * tint.rgb, 1.0);
     //return vec4(0.0, 0.0, 1.0, 1.0);
 }
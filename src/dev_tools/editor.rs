@@ -0,0 +1,604 @@
+//! A level editor for placing interactables on top of the level scenes. Press F2 while playing to
+//! toggle it, then fly around with WASD, Space and Ctrl and pick markers by looking at them and
+//! clicking. F5 writes the changes to the level overlay (see [`crate::game::spawn::overlay`]).
+
+use std::fs;
+
+use bevy::{
+    color::palettes::tailwind,
+    ecs::system::SystemParam,
+    input::{
+        common_conditions::input_just_pressed,
+        keyboard::{Key, KeyboardInput},
+        ButtonState,
+    },
+    prelude::*,
+    ui::Val::*,
+};
+use bevy_rapier3d::{pipeline::QueryFilter, plugin::RapierContext};
+use ron::ser::PrettyConfig;
+
+use crate::{
+    game::{
+        clock::{ClockSource, WorldClock},
+        cycles::Cycles,
+        data::{DataAsset, DataHandle},
+        input::PlayerInput,
+        levels::CurrentLevel,
+        logic::{CurrentCycle, Cycle, FreeFlight, Interactable},
+        movement::MovementController,
+        spawn::{
+            cycle_scene::CycleScene,
+            interactable::InteractableDefinitions,
+            markers::SpawnPoint,
+            overlay::{spawn_marker, LevelOverlay, OverlayMarker, OverlaySpawnPoint},
+            player::{CameraPivot, Player, PlayerCamera},
+        },
+    },
+    screen::PlayState,
+    ui::prelude::*,
+    AppSet,
+};
+
+const OVERLAY_HEADER: &str = "\
+// Interactables placed on top of the level scenes. Written by the level editor in dev builds
+// (F2 while playing), see `src/game/spawn/overlay.rs` for all options.
+";
+
+const FLY_SPEED: f32 = 10.0;
+const FAST_FLY_SPEED: f32 = 30.0;
+/// Markers are picked if they are at most this far away from the view direction, in radians.
+const PICK_ANGLE: f32 = 0.1;
+const PICK_DISTANCE: f32 = 100.0;
+/// Grabbed markers are put where the camera looks, at most this far away.
+const PLACE_DISTANCE: f32 = 30.0;
+/// How far markers are rotated at a time, in degrees.
+const ROTATION_STEP: f32 = 15.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_sub_state::<EditorState>();
+    app.enable_state_scoped_entities::<EditorState>();
+    app.init_resource::<Editor>();
+    app.add_systems(
+        Update,
+        toggle_editor.run_if(in_state(PlayState::InGame).and_then(input_just_pressed(KeyCode::F2))),
+    );
+    app.add_systems(OnEnter(EditorState::On), enter_editor);
+    app.add_systems(OnExit(EditorState::On), exit_editor);
+    app.add_systems(
+        Update,
+        (
+            block_game_input,
+            type_prompt,
+            (
+                fly,
+                select_marker,
+                edit_marker,
+                place_marker,
+                save_overlay.run_if(input_just_pressed(KeyCode::F5)),
+            )
+                .run_if(not(is_typing)),
+            move_grabbed_marker,
+            (draw_markers, update_panel),
+        )
+            .chain()
+            .in_set(AppSet::RecordInput)
+            .run_if(in_state(EditorState::On)),
+    );
+}
+
+#[derive(SubStates, Debug, Hash, PartialEq, Eq, Clone, Default)]
+#[source(PlayState = PlayState::InGame)]
+enum EditorState {
+    #[default]
+    Off,
+    On,
+}
+
+#[derive(Resource, Default, Debug)]
+struct Editor {
+    selected: Option<Entity>,
+    /// Whether the selected marker follows the camera.
+    grabbed: bool,
+    /// Index of the interactable definition that is placed next.
+    placing: usize,
+    /// The prompt that is being typed for the selected marker.
+    typing: Option<String>,
+    /// Whether the player couldn't move before the editor was opened, e.g. while rowing.
+    movement_was_disabled: bool,
+}
+
+#[derive(Component)]
+struct EditorPanel;
+
+fn is_typing(editor: Res<Editor>) -> bool {
+    editor.typing.is_some()
+}
+
+fn toggle_editor(state: Res<State<EditorState>>, mut next_state: ResMut<NextState<EditorState>>) {
+    next_state.set(match state.get() {
+        EditorState::Off => EditorState::On,
+        EditorState::On => EditorState::Off,
+    });
+}
+
+fn enter_editor(
+    mut commands: Commands,
+    mut editor: ResMut<Editor>,
+    mut clock: ResMut<WorldClock>,
+    mut player: Query<&mut MovementController, With<Player>>,
+) {
+    // Flying around shouldn't respawn the player, nor should the day end while editing.
+    commands.insert_resource(FreeFlight);
+    clock.pause(ClockSource::Editor);
+    let placing = editor.placing;
+    *editor = Editor {
+        placing,
+        ..default()
+    };
+    for mut controller in &mut player {
+        editor.movement_was_disabled = controller.disabled;
+        controller.disabled = true;
+    }
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Px(20.0),
+                left: Px(20.0),
+                padding: UiRect::all(Px(10.0)),
+                ..default()
+            },
+            background_color: BackgroundColor(ui_palette::NODE_BACKGROUND),
+            ..default()
+        })
+        .insert((
+            Name::new("Level editor"),
+            EditorPanel,
+            StateScoped(EditorState::On),
+        ));
+}
+
+fn exit_editor(
+    mut commands: Commands,
+    editor: Res<Editor>,
+    mut clock: ResMut<WorldClock>,
+    mut player: Query<&mut MovementController, With<Player>>,
+) {
+    commands.remove_resource::<FreeFlight>();
+    clock.resume(ClockSource::Editor);
+    for mut controller in &mut player {
+        controller.disabled = editor.movement_was_disabled;
+    }
+}
+
+/// Keep the keys the editor uses from interacting with the level.
+fn block_game_input(mut input: ResMut<PlayerInput>) {
+    input.interact = false;
+    input.fast_forward = false;
+    input.rewind = false;
+}
+
+fn fly(
+    time: Res<Time>,
+    input: Res<PlayerInput>,
+    keys: Res<ButtonInput<KeyCode>>,
+    pivot: Query<&Transform, (With<CameraPivot>, Without<Player>)>,
+    mut player: Query<&mut Transform, With<Player>>,
+) {
+    let (Ok(pivot), Ok(mut transform)) = (pivot.get_single(), player.get_single_mut()) else {
+        return;
+    };
+    let vertical =
+        keys.pressed(KeyCode::Space) as i8 as f32 - keys.pressed(KeyCode::ControlLeft) as i8 as f32;
+    let direction =
+        pivot.rotation * Vec3::new(input.movement.x, 0.0, -input.movement.y) + Vec3::Y * vertical;
+    let speed = match keys.pressed(KeyCode::ShiftLeft) {
+        true => FAST_FLY_SPEED,
+        false => FLY_SPEED,
+    };
+    transform.translation += direction.normalize_or_zero() * speed * time.delta_seconds();
+}
+
+/// Select the marker closest to the center of the view.
+fn select_marker(
+    mouse: Res<ButtonInput<MouseButton>>,
+    camera: Query<&GlobalTransform, With<PlayerCamera>>,
+    markers: Query<(Entity, &GlobalTransform), With<SpawnPoint>>,
+    mut editor: ResMut<Editor>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Ok(camera) = camera.get_single() else {
+        return;
+    };
+    editor.grabbed = false;
+    editor.selected = markers
+        .iter()
+        .filter_map(|(entity, marker)| {
+            let offset = marker.translation() - camera.translation();
+            let angle = offset.angle_between(*camera.forward());
+            (offset.length() <= PICK_DISTANCE && angle <= PICK_ANGLE).then_some((entity, angle))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity);
+}
+
+/// The markers in the level and their entries in the overlay.
+#[derive(SystemParam)]
+struct Markers<'w, 's> {
+    markers: Query<
+        'w,
+        's,
+        (
+            &'static Name,
+            &'static SpawnPoint,
+            &'static GlobalTransform,
+            Has<OverlaySpawnPoint>,
+        ),
+    >,
+    transforms: Query<'w, 's, &'static mut Transform, With<SpawnPoint>>,
+    global_transforms: Query<'w, 's, &'static GlobalTransform>,
+    parents: Query<'w, 's, &'static Parent>,
+    scene_cycles: Query<'w, 's, &'static Cycle, With<CycleScene>>,
+    overlays: ResMut<'w, Assets<LevelOverlay>>,
+    overlay_handle: Res<'w, DataHandle<LevelOverlay>>,
+}
+
+impl Markers<'_, '_> {
+    fn overlay(&self) -> Option<&LevelOverlay> {
+        self.overlays.get(&self.overlay_handle.0)
+    }
+
+    /// Change the overlay entry of a marker and move the marker to where the entry places it.
+    /// Markers from the level are added to the overlay the first time they are edited.
+    fn edit(&mut self, entity: Entity, change: impl FnOnce(&mut OverlayMarker)) {
+        let Ok((name, spawn_point, global_transform, _)) = self.markers.get(entity) else {
+            return;
+        };
+        let cycle = self
+            .parents
+            .iter_ancestors(entity)
+            .find_map(|ancestor| self.scene_cycles.get(ancestor).ok());
+        let Some(overlay) = self.overlays.get_mut(&self.overlay_handle.0) else {
+            warn!("The level overlay isn't loaded");
+            return;
+        };
+        let index = match overlay
+            .markers
+            .iter()
+            .position(|marker| marker.name == **name)
+        {
+            Some(index) => index,
+            None => {
+                let (_, rotation, translation) = global_transform.to_scale_rotation_translation();
+                overlay.markers.push(OverlayMarker {
+                    name: name.to_string(),
+                    interactable: spawn_point.0.clone(),
+                    cycle: cycle.map(|cycle| cycle.0.clone()),
+                    translation: translation.to_array(),
+                    yaw: rotation.to_euler(EulerRot::YXZ).0.to_degrees(),
+                    prompt: None,
+                });
+                overlay.markers.len() - 1
+            }
+        };
+        let marker = &mut overlay.markers[index];
+        change(marker);
+
+        // Markers from the level may be children of other objects.
+        let parent = self
+            .parents
+            .get(entity)
+            .ok()
+            .and_then(|parent| self.global_transforms.get(parent.get()).ok());
+        let placement = marker.transform();
+        if let Ok(mut transform) = self.transforms.get_mut(entity) {
+            *transform = match parent {
+                Some(parent) => Transform::from_matrix(
+                    parent.compute_matrix().inverse() * placement.compute_matrix(),
+                ),
+                None => placement,
+            };
+        }
+    }
+
+    /// Forget about a marker. Markers that only exist in the overlay are despawned.
+    fn remove(&mut self, entity: Entity, commands: &mut Commands) {
+        let Ok((name, _, _, from_overlay)) = self.markers.get(entity) else {
+            return;
+        };
+        if let Some(overlay) = self.overlays.get_mut(&self.overlay_handle.0) {
+            overlay.markers.retain(|marker| marker.name != **name);
+        }
+        match from_overlay {
+            true => commands.entity(entity).despawn_recursive(),
+            false => info!(
+                "\"{name}\" returns to its place in the level when the scene is spawned again"
+            ),
+        }
+    }
+}
+
+/// The point on the level the camera looks at.
+fn looked_at_point(
+    rapier_context: &RapierContext,
+    camera: &GlobalTransform,
+    ignored: &Query<(), Or<(With<Interactable>, With<Player>)>>,
+) -> Vec3 {
+    let filter = QueryFilter::new().predicate(&|entity| !ignored.contains(entity));
+    let distance = rapier_context
+        .cast_ray(
+            camera.translation(),
+            *camera.forward(),
+            PLACE_DISTANCE,
+            true,
+            filter,
+        )
+        .map_or(PLACE_DISTANCE / 4.0, |(_, distance)| distance);
+    camera.translation() + *camera.forward() * distance
+}
+
+fn edit_marker(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut editor: ResMut<Editor>,
+    mut markers: Markers,
+    interactables: Query<(&Parent, &Interactable)>,
+    cycles: Res<Cycles>,
+) {
+    let Some(selected) = editor.selected else {
+        return;
+    };
+    if keys.just_pressed(KeyCode::KeyM) {
+        editor.grabbed = !editor.grabbed;
+    }
+    if keys.just_pressed(KeyCode::KeyR) {
+        let step = match keys.pressed(KeyCode::ShiftLeft) {
+            true => -ROTATION_STEP,
+            false => ROTATION_STEP,
+        };
+        markers.edit(selected, |marker| {
+            marker.yaw = (marker.yaw + step).rem_euclid(360.0)
+        });
+    }
+    if keys.just_pressed(KeyCode::KeyC) {
+        // Go through every cycle, then back to all of them.
+        let options: Vec<_> = std::iter::once(None)
+            .chain(cycles.0.cycles.iter().map(|cycle| Some(cycle.id.clone())))
+            .collect();
+        markers.edit(selected, |marker| {
+            let index = options.iter().position(|cycle| *cycle == marker.cycle);
+            marker.cycle = options[index.map_or(0, |index| (index + 1) % options.len())].clone();
+        });
+    }
+    if keys.just_pressed(KeyCode::Enter) {
+        editor.typing = interactables
+            .iter()
+            .find(|(parent, _)| parent.get() == selected)
+            .map(|(_, interactable)| interactable.text.clone());
+    }
+    if keys.just_pressed(KeyCode::Delete) {
+        markers.remove(selected, &mut commands);
+        editor.selected = None;
+        editor.grabbed = false;
+    }
+}
+
+fn place_marker(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut editor: ResMut<Editor>,
+    markers: Markers,
+    definitions: Res<Assets<InteractableDefinitions>>,
+    definitions_handle: Res<DataHandle<InteractableDefinitions>>,
+    scenes: Query<(Entity, &Cycle), With<CycleScene>>,
+    camera: Query<&GlobalTransform, With<PlayerCamera>>,
+    rapier_context: Res<RapierContext>,
+    ignored: Query<(), Or<(With<Interactable>, With<Player>)>>,
+) {
+    let Some(definitions) = definitions.get(&definitions_handle.0) else {
+        return;
+    };
+    if keys.just_pressed(KeyCode::Tab) {
+        editor.placing = (editor.placing + 1) % definitions.interactables.len().max(1);
+    }
+    if !keys.just_pressed(KeyCode::KeyN) {
+        return;
+    }
+    let (Some(definition), Ok((scene, cycle)), Ok(camera), Some(overlay)) = (
+        definitions.interactables.get(editor.placing),
+        scenes.get_single(),
+        camera.get_single(),
+        markers.overlay(),
+    ) else {
+        return;
+    };
+    let name = (1..)
+        .map(|number| format!("{}_{number}", definition.id))
+        .find(|name| overlay.get(name).is_none())
+        .unwrap_or_default();
+    let marker = OverlayMarker {
+        name,
+        interactable: definition.id.clone(),
+        cycle: Some(cycle.0.clone()),
+        translation: looked_at_point(&rapier_context, camera, &ignored).to_array(),
+        yaw: 0.0,
+        prompt: None,
+    };
+    commands.entity(scene).with_children(|scene| {
+        editor.selected = Some(spawn_marker(scene, &marker));
+    });
+    // It is added to the overlay as soon as it is moved, which grabbing it does right away.
+    editor.grabbed = true;
+}
+
+fn move_grabbed_marker(
+    editor: Res<Editor>,
+    mut markers: Markers,
+    camera: Query<&GlobalTransform, With<PlayerCamera>>,
+    rapier_context: Res<RapierContext>,
+    ignored: Query<(), Or<(With<Interactable>, With<Player>)>>,
+) {
+    let (Some(selected), true, Ok(camera)) = (editor.selected, editor.grabbed, camera.get_single())
+    else {
+        return;
+    };
+    let point = looked_at_point(&rapier_context, camera, &ignored);
+    markers.edit(selected, |marker| marker.translation = point.to_array());
+}
+
+fn type_prompt(
+    mut events: EventReader<KeyboardInput>,
+    mut editor: ResMut<Editor>,
+    mut markers: Markers,
+    mut interactables: Query<(&Parent, &mut Interactable)>,
+    definitions: Res<Assets<InteractableDefinitions>>,
+    definitions_handle: Res<DataHandle<InteractableDefinitions>>,
+) {
+    let Editor {
+        selected: Some(selected),
+        typing: Some(text),
+        ..
+    } = &mut *editor
+    else {
+        // Drop the keys pressed meanwhile, like the Enter that starts typing.
+        events.clear();
+        return;
+    };
+    let selected = *selected;
+    let mut finished = false;
+    for event in events.read() {
+        if event.state != ButtonState::Pressed {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(character) => text.push_str(character),
+            Key::Space => text.push(' '),
+            Key::Backspace => {
+                text.pop();
+            }
+            Key::Enter => finished = true,
+            _ => {}
+        }
+    }
+    if !finished {
+        return;
+    }
+
+    // An empty prompt goes back to the one of the definition.
+    let prompt = Some(text.trim().to_string()).filter(|text| !text.is_empty());
+    editor.typing = None;
+    markers.edit(selected, |marker| marker.prompt.clone_from(&prompt));
+    let Ok((_, spawn_point, _, _)) = markers.markers.get(selected) else {
+        return;
+    };
+    let prompt = prompt.or_else(|| {
+        definitions
+            .get(&definitions_handle.0)
+            .and_then(|definitions| definitions.get(&spawn_point.0))
+            .map(|definition| definition.prompt.clone())
+    });
+    for (parent, mut interactable) in &mut interactables {
+        if let (true, Some(prompt)) = (parent.get() == selected, &prompt) {
+            interactable.text.clone_from(prompt);
+        }
+    }
+}
+
+fn save_overlay(markers: Markers, current_level: Res<CurrentLevel>) {
+    let Some(overlay) = markers.overlay() else {
+        return;
+    };
+    // The same file the game loads the overlay from.
+    let path = format!(
+        "{}/assets/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        LevelOverlay::path(&current_level)
+    );
+    let result = ron::ser::to_string_pretty(overlay, PrettyConfig::default())
+        .map_err(|error| error.to_string())
+        .and_then(|text| {
+            fs::write(&path, format!("{OVERLAY_HEADER}{text}\n")).map_err(|error| error.to_string())
+        });
+    match result {
+        Ok(()) => info!("Saved the level overlay to {path}"),
+        Err(error) => warn!("Could not save the level overlay to {path}: {error}"),
+    }
+}
+
+fn draw_markers(
+    mut gizmos: Gizmos,
+    editor: Res<Editor>,
+    markers: Query<(Entity, &GlobalTransform), With<SpawnPoint>>,
+) {
+    for (entity, transform) in &markers {
+        gizmos.axes(*transform, 1.0);
+        if editor.selected == Some(entity) {
+            let color = match editor.grabbed {
+                true => tailwind::AMBER_400,
+                false => tailwind::SKY_400,
+            };
+            gizmos.sphere(transform.translation(), Quat::IDENTITY, 0.5, color);
+        }
+    }
+}
+
+fn update_panel(
+    editor: Res<Editor>,
+    markers: Markers,
+    definitions: Res<Assets<InteractableDefinitions>>,
+    definitions_handle: Res<DataHandle<InteractableDefinitions>>,
+    interactables: Query<(&Parent, &Interactable)>,
+    current_cycle: Res<CurrentCycle>,
+    mut panel: Query<&mut Text, With<EditorPanel>>,
+) {
+    let Ok(mut panel) = panel.get_single_mut() else {
+        return;
+    };
+    let placing = definitions
+        .get(&definitions_handle.0)
+        .and_then(|definitions| definitions.interactables.get(editor.placing))
+        .map_or("nothing", |definition| definition.id.as_str());
+    let mut lines = vec![
+        format!("Level editor, cycle \"{}\"", current_cycle.0 .0),
+        format!("N: place {placing} (Tab for the next one)"),
+        "Click: select  F5: save  F2: leave".into(),
+    ];
+    if let Some((name, spawn_point, _, _)) = editor
+        .selected
+        .and_then(|selected| markers.markers.get(selected).ok())
+    {
+        let entry = markers.overlay().and_then(|overlay| overlay.get(name));
+        let cycle = match entry {
+            Some(OverlayMarker {
+                cycle: Some(cycle), ..
+            }) => cycle.as_str(),
+            Some(_) => "all",
+            None => "from the level",
+        };
+        let prompt = match &editor.typing {
+            Some(text) => format!("{text}_ (Enter to finish)"),
+            None => interactables
+                .iter()
+                .find(|(parent, _)| Some(parent.get()) == editor.selected)
+                .map_or(String::new(), |(_, interactable)| interactable.text.clone()),
+        };
+        lines.extend([
+            String::new(),
+            format!("Selected: {name} ({})", spawn_point.0),
+            format!("Cycle: {cycle}"),
+            format!("Prompt: {prompt}"),
+            "M: move  R: rotate  C: cycle  Enter: prompt  Del: remove".into(),
+        ]);
+    }
+    *panel = Text::from_section(
+        lines.join("\n"),
+        TextStyle {
+            font_size: 20.0,
+            color: ui_palette::LABEL_TEXT,
+            ..default()
+        },
+    );
+}
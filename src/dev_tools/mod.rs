@@ -1,9 +1,14 @@
 //! Development tools for the game. This plugin is only enabled in dev builds.
 
+mod editor;
+
 use bevy::{dev_tools::states::log_transitions, prelude::*};
 
 use crate::{
-    game::logic::{CurrentCycle, CycleChanged},
+    game::{
+        cycles::Cycles,
+        logic::{CurrentCycle, CycleChanged},
+    },
     screen::Screen,
     AppSet,
 };
@@ -13,15 +18,17 @@ pub(super) fn plugin(app: &mut App) {
     app.add_systems(Update, log_transitions::<Screen>);
     //app.add_plugins(RapierDebugRenderPlugin::default());
     app.add_systems(Update, handle_input.in_set(AppSet::RecordInput));
+    app.add_plugins(editor::plugin);
 }
 
 fn handle_input(
     input: Res<ButtonInput<KeyCode>>,
     current_cycle: Res<CurrentCycle>,
+    cycles: Res<Cycles>,
     mut commands: Commands,
 ) {
     if input.just_pressed(KeyCode::ArrowRight) {
-        let next_cycle = current_cycle.0.next();
+        let next_cycle = cycles.next(&current_cycle.0);
         commands.trigger(CycleChanged(next_cycle));
     }
 }
@@ -0,0 +1,400 @@
+//! Achievements that unlock in response to gameplay events and are kept across runs.
+
+use std::{fs, io, path::PathBuf};
+
+use bevy::{prelude::*, ui::Val::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{screen::Screen, ui::prelude::*, AppSet};
+
+use super::{
+    assets::SfxKey,
+    audio::sfx::PlaySfx,
+    inventory::{Inventory, ItemAction, Items},
+    logic::{CycleChanged, Footstep, Interacted},
+    records::{record_run, LastRun},
+    save::{data_dir, unix_timestamp, DisableSaving, SaveError, SaveGame},
+};
+
+/// Bump this whenever [`AchievementData`] changes in a way that old files can't be read anymore.
+pub const ACHIEVEMENTS_VERSION: u32 = 1;
+
+/// How long a toast stays on screen, in seconds.
+const TOAST_DURATION: f32 = 5.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.insert_resource(AchievementData::load());
+    app.add_systems(Startup, spawn_toast_area);
+    app.observe(unlock);
+    app.observe(write_achievements);
+    app.observe(count_cycle_changes);
+    app.observe(count_interactions);
+    app.observe(count_steps);
+    app.observe(count_rows);
+    app.add_systems(
+        OnEnter(Screen::Credits),
+        check_finished_run.after(record_run),
+    );
+    app.add_systems(Update, tick_toasts.in_set(AppSet::TickTimers));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Achievement {
+    Finished,
+    NoHourglass,
+    UnderHundredThousandYears,
+    Ferryman,
+    Wanderer,
+    HandsOn,
+    Witness,
+}
+
+impl Achievement {
+    /// All achievements, in the order they are listed.
+    pub const ALL: [Achievement; 7] = [
+        Achievement::Finished,
+        Achievement::NoHourglass,
+        Achievement::UnderHundredThousandYears,
+        Achievement::Ferryman,
+        Achievement::Wanderer,
+        Achievement::HandsOn,
+        Achievement::Witness,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Achievement::Finished => "Legacy",
+            Achievement::NoHourglass => "Patience",
+            Achievement::UnderHundredThousandYears => "Ahead of Time",
+            Achievement::Ferryman => "Ferryman",
+            Achievement::Wanderer => "Wanderer",
+            Achievement::HandsOn => "Hands On",
+            Achievement::Witness => "Witness",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Achievement::Finished => "Finish your project",
+            Achievement::NoHourglass => "Finish without taking the hourglass",
+            Achievement::UnderHundredThousandYears => "Finish in under 100,000 years",
+            Achievement::Ferryman => "Row the boat 10 times",
+            Achievement::Wanderer => "Take 5,000 steps",
+            Achievement::HandsOn => "Interact with something 50 times",
+            Achievement::Witness => "See the world end 25 times",
+        }
+    }
+
+    /// The counter this achievement tracks and the value that unlocks it.
+    pub fn goal(self) -> Option<(Counter, u32)> {
+        match self {
+            Achievement::Ferryman => Some((Counter::Rows, 10)),
+            Achievement::Wanderer => Some((Counter::Steps, 5000)),
+            Achievement::HandsOn => Some((Counter::Interactions, 50)),
+            Achievement::Witness => Some((Counter::CycleChanges, 25)),
+            _ => None,
+        }
+    }
+}
+
+/// Events that are counted across all runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counter {
+    Rows,
+    Steps,
+    Interactions,
+    CycleChanges,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct Counters {
+    pub rows: u32,
+    pub steps: u32,
+    pub interactions: u32,
+    pub cycle_changes: u32,
+}
+
+impl Counters {
+    pub fn get(&self, counter: Counter) -> u32 {
+        match counter {
+            Counter::Rows => self.rows,
+            Counter::Steps => self.steps,
+            Counter::Interactions => self.interactions,
+            Counter::CycleChanges => self.cycle_changes,
+        }
+    }
+
+    fn get_mut(&mut self, counter: Counter) -> &mut u32 {
+        match counter {
+            Counter::Rows => &mut self.rows,
+            Counter::Steps => &mut self.steps,
+            Counter::Interactions => &mut self.interactions,
+            Counter::CycleChanges => &mut self.cycle_changes,
+        }
+    }
+}
+
+/// Unlocked achievements and progress towards the others, as stored on disk.
+#[derive(Resource, Serialize, Deserialize, Debug)]
+pub struct AchievementData {
+    pub version: u32,
+    /// Unlocked achievements and when they were unlocked, in seconds since the Unix epoch.
+    pub unlocked: Vec<(Achievement, u64)>,
+    #[serde(default)]
+    pub counters: Counters,
+}
+
+impl Default for AchievementData {
+    fn default() -> Self {
+        Self {
+            version: ACHIEVEMENTS_VERSION,
+            unlocked: vec![],
+            counters: default(),
+        }
+    }
+}
+
+impl AchievementData {
+    pub fn load() -> Self {
+        match Self::read() {
+            Ok(data) => data,
+            Err(SaveError::Io(error)) if error.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(error) => {
+                warn!("Ignoring achievements file: {error}");
+                Self::default()
+            }
+        }
+    }
+
+    fn read() -> Result<Self, SaveError> {
+        let contents = fs::read_to_string(achievements_path())?;
+        let data: AchievementData = ron::from_str(&contents)?;
+        if data.version != ACHIEVEMENTS_VERSION {
+            return Err(SaveError::UnsupportedVersion(data.version));
+        }
+        Ok(data)
+    }
+
+    pub fn write(&self) -> Result<(), SaveError> {
+        fs::create_dir_all(data_dir())?;
+        let contents = ron::ser::to_string_pretty(self, default())?;
+        fs::write(achievements_path(), contents)?;
+        Ok(())
+    }
+
+    /// When the achievement was unlocked, if it was.
+    pub fn unlocked_at(&self, achievement: Achievement) -> Option<u64> {
+        self.unlocked
+            .iter()
+            .find(|(unlocked, _)| *unlocked == achievement)
+            .map(|(_, timestamp)| *timestamp)
+    }
+
+    /// Count an event and return the locked achievements whose goal has been reached.
+    /// Counters that passed a goal while it couldn't be unlocked still unlock it on the next event.
+    fn count(&mut self, counter: Counter) -> impl Iterator<Item = Achievement> + '_ {
+        let value = self.counters.get_mut(counter);
+        *value = value.saturating_add(1);
+        let value = *value;
+        Achievement::ALL.into_iter().filter(move |achievement| {
+            achievement
+                .goal()
+                .is_some_and(|(goal_counter, goal)| goal_counter == counter && value >= goal)
+                && self.unlocked_at(*achievement).is_none()
+        })
+    }
+}
+
+fn achievements_path() -> PathBuf {
+    data_dir().join("achievements.ron")
+}
+
+/// Trigger this event to unlock an achievement. Nothing happens if it was already unlocked.
+#[derive(Event)]
+pub struct UnlockAchievement(pub Achievement);
+
+#[derive(Component)]
+struct ToastArea;
+
+#[derive(Component)]
+struct Toast(Timer);
+
+fn unlock(
+    trigger: Trigger<UnlockAchievement>,
+    mut commands: Commands,
+    mut data: ResMut<AchievementData>,
+    disable_saving: Option<Res<DisableSaving>>,
+    toast_area: Query<Entity, With<ToastArea>>,
+) {
+    let achievement = trigger.event().0;
+    // Replayed runs don't count.
+    if disable_saving.is_some() || data.unlocked_at(achievement).is_some() {
+        return;
+    }
+    data.unlocked.push((achievement, unix_timestamp()));
+    if let Err(error) = data.write() {
+        warn!("Could not save achievements: {error}");
+    }
+
+    let Ok(toast_area) = toast_area.get_single() else {
+        return;
+    };
+    commands.entity(toast_area).with_children(|toasts| {
+        toasts
+            .spawn((
+                Name::new("Toast"),
+                Toast(Timer::from_seconds(TOAST_DURATION, TimerMode::Once)),
+                NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        padding: UiRect::all(Px(10.0)),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(ui_palette::NODE_BACKGROUND),
+                    border_radius: BorderRadius::all(Px(8.0)),
+                    ..default()
+                },
+            ))
+            .with_children(|toast| {
+                toast.spawn(TextBundle::from_section(
+                    format!("Achievement unlocked: {}", achievement.name()),
+                    TextStyle {
+                        font_size: 24.0,
+                        color: ui_palette::HEADER_TEXT,
+                        ..default()
+                    },
+                ));
+                toast.spawn(TextBundle::from_section(
+                    achievement.description(),
+                    TextStyle {
+                        font_size: 20.0,
+                        color: ui_palette::BUTTON_TEXT,
+                        ..default()
+                    },
+                ));
+            });
+    });
+}
+
+/// Counters are only written together with the run, so they don't hit the disk on every step.
+fn write_achievements(
+    _trigger: Trigger<SaveGame>,
+    data: Res<AchievementData>,
+    disable_saving: Option<Res<DisableSaving>>,
+) {
+    if disable_saving.is_some() {
+        return;
+    }
+    if let Err(error) = data.write() {
+        warn!("Could not save achievements: {error}");
+    }
+}
+
+fn count(
+    commands: &mut Commands,
+    data: &mut AchievementData,
+    disable_saving: Option<Res<DisableSaving>>,
+    counter: Counter,
+) {
+    // Replayed runs don't count.
+    if disable_saving.is_some() {
+        return;
+    }
+    for achievement in data.count(counter) {
+        commands.trigger(UnlockAchievement(achievement));
+    }
+}
+
+fn count_cycle_changes(
+    _trigger: Trigger<CycleChanged>,
+    mut commands: Commands,
+    mut data: ResMut<AchievementData>,
+    disable_saving: Option<Res<DisableSaving>>,
+) {
+    count(
+        &mut commands,
+        &mut data,
+        disable_saving,
+        Counter::CycleChanges,
+    );
+}
+
+fn count_interactions(
+    _trigger: Trigger<Interacted>,
+    mut commands: Commands,
+    mut data: ResMut<AchievementData>,
+    disable_saving: Option<Res<DisableSaving>>,
+) {
+    count(
+        &mut commands,
+        &mut data,
+        disable_saving,
+        Counter::Interactions,
+    );
+}
+
+fn count_steps(
+    _trigger: Trigger<Footstep>,
+    mut commands: Commands,
+    mut data: ResMut<AchievementData>,
+    disable_saving: Option<Res<DisableSaving>>,
+) {
+    count(&mut commands, &mut data, disable_saving, Counter::Steps);
+}
+
+fn count_rows(
+    trigger: Trigger<PlaySfx>,
+    mut commands: Commands,
+    mut data: ResMut<AchievementData>,
+    disable_saving: Option<Res<DisableSaving>>,
+) {
+    if matches!(trigger.event(), PlaySfx::Key(SfxKey::Row)) {
+        count(&mut commands, &mut data, disable_saving, Counter::Rows);
+    }
+}
+
+fn check_finished_run(
+    mut commands: Commands,
+    last_run: Option<Res<LastRun>>,
+    inventory: Res<Inventory>,
+    items: Res<Items>,
+) {
+    let Some(last_run) = last_run else {
+        return;
+    };
+    commands.trigger(UnlockAchievement(Achievement::Finished));
+    if !inventory.can(ItemAction::FastForward, &items) {
+        commands.trigger(UnlockAchievement(Achievement::NoHourglass));
+    }
+    if last_run.record.years < 100_000 {
+        commands.trigger(UnlockAchievement(Achievement::UnderHundredThousandYears));
+    }
+}
+
+fn spawn_toast_area(mut commands: Commands) {
+    commands.spawn((
+        Name::new("Toast area"),
+        ToastArea,
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Px(20.0),
+                top: Px(20.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Px(10.0),
+                ..default()
+            },
+            // Show toasts above every screen.
+            z_index: ZIndex::Global(10),
+            ..default()
+        },
+    ));
+}
+
+fn tick_toasts(mut commands: Commands, time: Res<Time>, mut toasts: Query<(Entity, &mut Toast)>) {
+    for (entity, mut toast) in &mut toasts {
+        if toast.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -3,6 +3,7 @@ use bevy::{
     render::texture::{ImageLoaderSettings, ImageSampler},
     utils::HashMap,
 };
+use serde::{Deserialize, Serialize};
 
 pub(super) fn plugin(app: &mut App) {
     app.register_type::<HandleMap<ImageKey>>();
@@ -40,7 +41,7 @@ impl FromWorld for HandleMap<ImageKey> {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Reflect, Debug, Serialize, Deserialize)]
 pub enum SfxKey {
     ButtonHover,
     ButtonPress,
@@ -173,3 +174,19 @@ impl<K: AssetKey> HandleMap<K> {
             .all(|x| asset_server.is_loaded_with_dependencies(x))
     }
 }
+
+/// Update an asset only if `changed` says it is out of date. Borrowing an asset mutably marks it
+/// as modified, which would upload it to the GPU again every frame.
+pub fn update_asset<A: Asset>(
+    assets: &mut Assets<A>,
+    handle: &Handle<A>,
+    changed: impl FnOnce(&A) -> bool,
+    update: impl FnOnce(&mut A),
+) {
+    if !assets.get(handle).is_some_and(changed) {
+        return;
+    }
+    if let Some(asset) = assets.get_mut(handle) {
+        update(asset);
+    }
+}
@@ -13,21 +13,33 @@ fn play_soundtrack(
     soundtrack_handles: Res<HandleMap<SoundtrackKey>>,
     soundtrack_query: Query<Entity, With<IsSoundtrack>>,
 ) {
-    if let PlaySoundtrack::Key(soundtrack_key) = trigger.event() {
-        let mut audio_player = commands.spawn((AudioSourceBundle {
-            source: soundtrack_handles[soundtrack_key].clone_weak(),
-            settings: PlaybackSettings {
-                mode: PlaybackMode::Loop,
-                ..default()
-            },
-        },));
-        if soundtrack_key == &SoundtrackKey::CycleOne {
-            audio_player.insert(IsSoundtrack);
+    let settings = PlaybackSettings {
+        mode: PlaybackMode::Loop,
+        ..default()
+    };
+    match trigger.event() {
+        PlaySoundtrack::Key(soundtrack_key) => {
+            let mut audio_player = commands.spawn((AudioSourceBundle {
+                source: soundtrack_handles[soundtrack_key].clone_weak(),
+                settings,
+            },));
+            if soundtrack_key == &SoundtrackKey::CycleOne {
+                audio_player.insert(IsSoundtrack);
+            }
         }
-    } else {
-        println!("Desp");
-        for track in soundtrack_query.iter() {
-            commands.entity(track).despawn();
+        PlaySoundtrack::Handle(source) => {
+            commands
+                .spawn(AudioSourceBundle {
+                    source: source.clone(),
+                    settings,
+                })
+                .insert(IsSoundtrack);
+        }
+        PlaySoundtrack::Disable => {
+            println!("Desp");
+            for track in soundtrack_query.iter() {
+                commands.entity(track).despawn();
+            }
         }
     }
 }
@@ -38,6 +50,8 @@ fn play_soundtrack(
 #[derive(Event)]
 pub enum PlaySoundtrack {
     Key(SoundtrackKey),
+    /// Music that isn't known in advance, like the soundtrack of a cycle.
+    Handle(Handle<AudioSource>),
     Disable,
 }
 
@@ -0,0 +1,223 @@
+//! A HUD calendar that shows the era and year the island is in, next to an hourglass whose sand
+//! runs out as the day approaches its end. Press H in game to show or hide it.
+
+use std::f32::consts::TAU;
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
+
+use crate::{screen::Screen, ui::prelude::*};
+
+use super::{
+    clock::WorldClock,
+    cycles::Cycles,
+    logic::{CurrentCycle, YEARS_PER_CYCLE},
+};
+
+/// The hourglass starts to pulse once this much of the day is left.
+const WARNING_THRESHOLD: f32 = 0.1;
+/// Pulses per second of the warning.
+const WARNING_FREQUENCY: f32 = 2.0;
+const WARNING_COLOR: Color = Color::srgb(0.9, 0.35, 0.2);
+const CHAMBER_WIDTH: f32 = 24.0;
+const CHAMBER_HEIGHT: f32 = 20.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<Calendar>();
+    app.add_systems(OnEnter(Screen::Playing), spawn_calendar);
+    app.add_systems(
+        Update,
+        (
+            toggle_calendar.run_if(input_just_pressed(KeyCode::KeyH)),
+            update_calendar,
+            drain_hourglass,
+        )
+            .chain()
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+#[derive(Resource)]
+pub struct Calendar {
+    pub visible: bool,
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Self { visible: true }
+    }
+}
+
+#[derive(Component)]
+struct CalendarOverlay;
+
+#[derive(Component)]
+struct CalendarText;
+
+/// The sand in one of the hourglass' chambers.
+#[derive(Component)]
+enum Sand {
+    /// What is left of the day.
+    Top,
+    /// What has passed of the day.
+    Bottom,
+}
+
+fn spawn_calendar(mut commands: Commands, calendar: Res<Calendar>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                right: Px(20.0),
+                top: Px(20.0),
+                padding: UiRect::all(Px(10.0)),
+                column_gap: Px(10.0),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            background_color: BackgroundColor(ui_palette::NODE_BACKGROUND),
+            visibility: match calendar.visible {
+                true => Visibility::Inherited,
+                false => Visibility::Hidden,
+            },
+            ..default()
+        })
+        .insert((Name::new("Calendar"), StateScoped(Screen::Playing)))
+        .insert(CalendarOverlay)
+        .with_children(|overlay| {
+            overlay
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    ..default()
+                })
+                .insert(Name::new("Hourglass"))
+                .with_children(|hourglass| {
+                    spawn_chamber(hourglass, Sand::Top);
+                    hourglass.spawn(NodeBundle {
+                        style: Style {
+                            width: Px(4.0),
+                            height: Px(4.0),
+                            ..default()
+                        },
+                        background_color: BackgroundColor(ui_palette::LABEL_TEXT),
+                        ..default()
+                    });
+                    spawn_chamber(hourglass, Sand::Bottom);
+                });
+            overlay
+                .spawn(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 20.0,
+                        color: ui_palette::LABEL_TEXT,
+                        ..default()
+                    },
+                ))
+                .insert(CalendarText);
+        });
+}
+
+fn spawn_chamber(hourglass: &mut ChildBuilder, sand: Sand) {
+    hourglass
+        .spawn(NodeBundle {
+            style: Style {
+                width: Px(CHAMBER_WIDTH),
+                height: Px(CHAMBER_HEIGHT),
+                flex_direction: FlexDirection::Column,
+                // Sand always settles at the bottom of a chamber.
+                justify_content: JustifyContent::FlexEnd,
+                border: UiRect::all(Px(1.0)),
+                ..default()
+            },
+            border_color: BorderColor(ui_palette::LABEL_TEXT),
+            ..default()
+        })
+        .with_children(|chamber| {
+            chamber
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Percent(100.0),
+                        height: Percent(0.0),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(ui_palette::LABEL_TEXT),
+                    ..default()
+                })
+                .insert(sand);
+        });
+}
+
+fn toggle_calendar(
+    mut calendar: ResMut<Calendar>,
+    mut overlay: Query<&mut Visibility, With<CalendarOverlay>>,
+) {
+    calendar.visible = !calendar.visible;
+    for mut visibility in &mut overlay {
+        *visibility = match calendar.visible {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        };
+    }
+}
+
+/// How much of the day has passed, as shown by the calendar. Time no longer moves on once a
+/// terminal cycle is reached, so neither does the calendar.
+fn day_passed(clock: &WorldClock, current_cycle: &CurrentCycle, cycles: &Cycles) -> f32 {
+    match cycles.is_terminal(&current_cycle.0) {
+        true => 0.0,
+        false => clock.progress,
+    }
+}
+
+fn update_calendar(
+    calendar: Res<Calendar>,
+    clock: Res<WorldClock>,
+    current_cycle: Res<CurrentCycle>,
+    cycles: Res<Cycles>,
+    mut text: Query<&mut Text, With<CalendarText>>,
+) {
+    if !calendar.visible {
+        return;
+    }
+    let Ok(mut text) = text.get_single_mut() else {
+        return;
+    };
+    let day_passed = day_passed(&clock, &current_cycle, &cycles);
+    let year = ((current_cycle.1 as f32 + day_passed) * YEARS_PER_CYCLE as f32) as usize;
+    let value = format!("Era {}\nYear {year}", current_cycle.1 + 1);
+    if text.sections[0].value != value {
+        text.sections[0].value = value;
+    }
+}
+
+fn drain_hourglass(
+    calendar: Res<Calendar>,
+    clock: Res<WorldClock>,
+    current_cycle: Res<CurrentCycle>,
+    cycles: Res<Cycles>,
+    time: Res<Time<Real>>,
+    mut sand: Query<(&Sand, &mut Style, &mut BackgroundColor)>,
+) {
+    if !calendar.visible {
+        return;
+    }
+    let day_passed = day_passed(&clock, &current_cycle, &cycles);
+    let color = match 1.0 - day_passed <= WARNING_THRESHOLD {
+        true => {
+            let pulse = (time.elapsed_seconds() * WARNING_FREQUENCY * TAU).sin() * 0.5 + 0.5;
+            ui_palette::LABEL_TEXT.mix(&WARNING_COLOR, pulse)
+        }
+        false => ui_palette::LABEL_TEXT,
+    };
+    for (sand, mut style, mut background) in &mut sand {
+        let fill = match sand {
+            Sand::Top => 1.0 - day_passed,
+            Sand::Bottom => day_passed,
+        };
+        style.height = Percent(fill * 100.0);
+        background.0 = color;
+    }
+}
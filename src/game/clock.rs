@@ -0,0 +1,155 @@
+//! The world clock measures how far the current day has progressed. Other systems slow it down,
+//! speed it up or stop it through [`WorldClock`] and react to the times of day it announces,
+//! rather than advancing time themselves.
+
+use bevy::prelude::*;
+
+use crate::{screen::PlayState, AppSet};
+
+/// Real time a day takes at normal speed, in seconds.
+pub const DAY_LENGTH: f32 = 60.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<WorldClock>();
+    app.add_systems(
+        Update,
+        advance_clock
+            .in_set(AppSet::TickTimers)
+            .run_if(in_state(PlayState::InGame)),
+    );
+}
+
+/// Something that changes how fast time passes, or stops it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockSource {
+    Hourglass,
+    Rowing,
+    Impact,
+    #[cfg(feature = "dev")]
+    Editor,
+}
+
+#[derive(Resource, Debug)]
+pub struct WorldClock {
+    /// How far the day has progressed, from 0 at dawn to 1 at the next dawn.
+    pub progress: f32,
+    /// Real time a day takes at normal speed, in seconds.
+    pub day_length: f32,
+    scales: Vec<(ClockSource, f32)>,
+    pauses: Vec<ClockSource>,
+}
+
+impl Default for WorldClock {
+    fn default() -> Self {
+        Self {
+            progress: 0.0,
+            day_length: DAY_LENGTH,
+            scales: vec![],
+            pauses: vec![],
+        }
+    }
+}
+
+impl WorldClock {
+    /// Make time pass `scale` times as fast until the scale of `source` is cleared.
+    /// Scales of different sources multiply, a negative scale makes time run backwards.
+    pub fn set_scale(&mut self, source: ClockSource, scale: f32) {
+        self.clear_scale(source);
+        self.scales.push((source, scale));
+    }
+
+    pub fn clear_scale(&mut self, source: ClockSource) {
+        self.scales.retain(|(other, _)| *other != source);
+    }
+
+    /// Stop time until every source that paused it has resumed it.
+    pub fn pause(&mut self, source: ClockSource) {
+        if !self.pauses.contains(&source) {
+            self.pauses.push(source);
+        }
+    }
+
+    pub fn resume(&mut self, source: ClockSource) {
+        self.pauses.retain(|other| *other != source);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        !self.pauses.is_empty()
+    }
+
+    /// How much faster than normal time currently passes.
+    pub fn scale(&self) -> f32 {
+        match self.is_paused() {
+            true => 0.0,
+            false => self.scales.iter().map(|(_, scale)| scale).product(),
+        }
+    }
+
+    /// Start a new day at the given progress, without any scales or pauses.
+    pub fn restart(&mut self, progress: f32) {
+        self.progress = progress;
+        self.scales.clear();
+        self.pauses.clear();
+    }
+}
+
+/// Points of the day that are announced when the clock passes them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DayPhase {
+    Dawn,
+    Noon,
+    Dusk,
+    Midnight,
+}
+
+impl DayPhase {
+    pub const ALL: [DayPhase; 4] = [
+        DayPhase::Dawn,
+        DayPhase::Noon,
+        DayPhase::Dusk,
+        DayPhase::Midnight,
+    ];
+
+    /// The [`WorldClock::progress`] at which the phase is reached.
+    pub fn progress(self) -> f32 {
+        match self {
+            DayPhase::Dawn => 0.0,
+            DayPhase::Noon => 0.25,
+            DayPhase::Dusk => 0.5,
+            DayPhase::Midnight => 0.75,
+        }
+    }
+}
+
+/// Triggered whenever the clock passes one of the [`DayPhase`]s.
+#[derive(Event, Debug)]
+pub struct DayPhaseReached(pub DayPhase);
+
+/// Triggered when the day is over, right after the clock has started the next one.
+#[derive(Event, Debug)]
+pub struct DayEnded;
+
+pub fn advance_clock(mut commands: Commands, time: Res<Time>, mut clock: ResMut<WorldClock>) {
+    let scale = clock.scale();
+    if scale == 0.0 {
+        return;
+    }
+    let from = clock.progress;
+    // Time can't be rewound past the dawn of the current day.
+    let to = (from + time.delta_seconds() * scale / clock.day_length).max(0.0);
+    for phase in DayPhase::ALL {
+        // Dawn is reached at the end of the day rather than at its start.
+        let at = match phase {
+            DayPhase::Dawn => 1.0,
+            _ => phase.progress(),
+        };
+        if from < at && at <= to {
+            commands.trigger(DayPhaseReached(phase));
+        }
+    }
+    clock.progress = to;
+    if to >= 1.0 {
+        clock.progress -= 1.0;
+        commands.trigger(DayEnded);
+    }
+}
@@ -0,0 +1,195 @@
+//! The comet is the clock of doom: it closes in on the island as the day goes on, and each cycle
+//! it starts out a little closer. When the day is over it strikes, the screen flashes white and
+//! the next cycle begins behind the flash. Time stands still and input is ignored meanwhile.
+
+use std::f32::consts::PI;
+
+use bevy::{prelude::*, ui::Val::*};
+
+use crate::{
+    screen::{PlayState, Screen},
+    AppSet,
+};
+
+use super::{
+    assets::update_asset,
+    clock::{ClockSource, DayEnded, WorldClock},
+    cycles::Cycles,
+    input::BlockInput,
+    logic::{CurrentCycle, CycleChanged},
+    progression::{Progress, MONUMENT_FLAG},
+    save::load_game,
+    spawn::comet::{Comet, CometGlow, CometTail},
+};
+
+/// Where the comet strikes.
+const IMPACT_POINT: Vec3 = Vec3::ZERO;
+/// How much of its approach the comet has already made at dawn, per elapsed cycle.
+const HEAD_START_PER_CYCLE: f32 = 0.05;
+const MAX_HEAD_START: f32 = 0.4;
+/// How many times its resting size the comet grows until it strikes.
+const GROWTH: f32 = 3.0;
+/// Emissive strength of the glow right before the impact.
+const GLOW: f32 = 20.0;
+const GLOW_COLOR: LinearRgba = LinearRgba::rgb(1.0, 0.6, 0.3);
+/// Length of the tail right before the impact, relative to the comet.
+const TAIL_LENGTH: f32 = 60.0;
+/// Real time the impact takes, in seconds. The next cycle starts halfway through.
+const IMPACT_DURATION: f32 = 1.5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.observe(start_impact);
+    app.add_systems(
+        OnEnter(Screen::Playing),
+        (reset_impact.after(load_game), spawn_flash),
+    );
+    app.add_systems(
+        Update,
+        play_impact
+            .in_set(AppSet::TickTimers)
+            .run_if(resource_exists::<Impact>.and_then(in_state(PlayState::InGame))),
+    );
+    app.add_systems(
+        Update,
+        (approach_comet, flash)
+            .in_set(AppSet::Update)
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+/// The comet striking the island, from the end of a day until the next cycle has begun.
+#[derive(Resource, Debug)]
+struct Impact {
+    timer: Timer,
+    /// Whether the next cycle has begun.
+    struck: bool,
+}
+
+#[derive(Component)]
+struct ImpactFlash;
+
+/// How far the comet has come from its resting place toward the island, from 0 to 1.
+/// It speeds up toward the end of the day.
+fn approach(progress: f32, cycles_elapsed: usize) -> f32 {
+    let head_start = (cycles_elapsed as f32 * HEAD_START_PER_CYCLE).min(MAX_HEAD_START);
+    head_start + (1.0 - head_start) * progress.clamp(0.0, 1.0).powi(3)
+}
+
+fn reset_impact(mut commands: Commands) {
+    commands.remove_resource::<Impact>();
+    commands.remove_resource::<BlockInput>();
+}
+
+fn spawn_flash(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Percent(100.0),
+                height: Percent(100.0),
+                ..default()
+            },
+            background_color: BackgroundColor(Color::WHITE.with_alpha(0.0)),
+            z_index: ZIndex::Global(20),
+            ..default()
+        })
+        .insert((Name::new("Impact flash"), StateScoped(Screen::Playing)))
+        .insert(ImpactFlash);
+}
+
+/// Let the comet strike once the day is over. In a terminal cycle there is nothing left for it
+/// to destroy, so the cycle changes right away.
+fn start_impact(
+    _trigger: Trigger<DayEnded>,
+    mut commands: Commands,
+    mut clock: ResMut<WorldClock>,
+    current_cycle: Res<CurrentCycle>,
+    progress: Res<Progress>,
+    cycles: Res<Cycles>,
+) {
+    if cycles.is_terminal(&current_cycle.0) {
+        let next_cycle = cycles.after_day(&current_cycle.0, progress.has_flag(MONUMENT_FLAG));
+        commands.trigger(CycleChanged(next_cycle));
+        return;
+    }
+    clock.pause(ClockSource::Impact);
+    commands.insert_resource(Impact {
+        timer: Timer::from_seconds(IMPACT_DURATION, TimerMode::Once),
+        struck: false,
+    });
+    commands.insert_resource(BlockInput);
+}
+
+fn play_impact(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut impact: ResMut<Impact>,
+    mut clock: ResMut<WorldClock>,
+    current_cycle: Res<CurrentCycle>,
+    progress: Res<Progress>,
+    cycles: Res<Cycles>,
+) {
+    impact.timer.tick(time.delta());
+    // Change the cycle at the height of the flash, so the player doesn't see the world change.
+    if !impact.struck && impact.timer.fraction() >= 0.5 {
+        impact.struck = true;
+        let next_cycle = cycles.after_day(&current_cycle.0, progress.has_flag(MONUMENT_FLAG));
+        commands.trigger(CycleChanged(next_cycle));
+    }
+    if impact.timer.finished() {
+        clock.resume(ClockSource::Impact);
+        commands.remove_resource::<Impact>();
+        commands.remove_resource::<BlockInput>();
+    }
+}
+
+fn approach_comet(
+    clock: Res<WorldClock>,
+    current_cycle: Res<CurrentCycle>,
+    cycles: Res<Cycles>,
+    impact: Option<Res<Impact>>,
+    mut comets: Query<(&Comet, &mut Transform), Without<CometTail>>,
+    mut tails: Query<&mut Transform, With<CometTail>>,
+    effects: Query<&Handle<StandardMaterial>, Or<(With<CometGlow>, With<CometTail>)>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // The comet stays put in a terminal cycle, and stays on the island until the flash hides it.
+    let t = match (cycles.is_terminal(&current_cycle.0), impact) {
+        (true, _) => 0.0,
+        (false, Some(impact)) if !impact.struck => 1.0,
+        (false, _) => approach(clock.progress, current_cycle.1),
+    };
+    for (comet, mut transform) in &mut comets {
+        transform.translation = comet.rest.translation.lerp(IMPACT_POINT, t);
+        transform.scale = comet.rest.scale * (1.0 + t * GROWTH);
+        // The tail points away from where the comet is headed, in the comet's own space.
+        let away = (comet.rest.translation - IMPACT_POINT)
+            .try_normalize()
+            .unwrap_or(Vec3::Y);
+        let away = comet.rest.rotation.inverse() * away;
+        let length = (TAIL_LENGTH * t).max(f32::EPSILON);
+        for mut tail in &mut tails {
+            tail.translation = away * length / 2.0;
+            tail.rotation = Quat::from_rotation_arc(Vec3::Y, away);
+            tail.scale = Vec3::new(1.0, length, 1.0);
+        }
+    }
+    let emissive = GLOW_COLOR * (GLOW * t * t);
+    for effect in &effects {
+        update_asset(
+            &mut materials,
+            effect,
+            |material| material.emissive != emissive,
+            |material| material.emissive = emissive,
+        );
+    }
+}
+
+fn flash(impact: Option<Res<Impact>>, mut flashes: Query<&mut BackgroundColor, With<ImpactFlash>>) {
+    let alpha = impact.map_or(0.0, |impact| (impact.timer.fraction() * PI).sin());
+    for mut background in &mut flashes {
+        if background.0.alpha() != alpha {
+            background.0.set_alpha(alpha);
+        }
+    }
+}
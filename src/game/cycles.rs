@@ -0,0 +1,215 @@
+//! The eras the island goes through are defined in `assets/data/island.cycles.ron`, so chapters
+//! with any number of cycles can be authored without touching the code. The definitions are
+//! copied into the [`Cycles`] resource whenever the file is loaded or changed.
+
+use bevy::{asset::LoadContext, prelude::*};
+use serde::Deserialize;
+
+use crate::screen::Screen;
+
+use super::{
+    audio::soundtrack::PlaySoundtrack,
+    data::{init_data_file, unique_ids, DataAsset, DataError, DataHandle},
+    logic::{CurrentCycle, Cycle},
+    save::load_game,
+    spawn::level::SkyMaterial,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    init_data_file::<CycleDefinitions>(app);
+    app.init_resource::<Cycles>();
+    app.add_systems(PreUpdate, update_cycles);
+    app.add_systems(OnEnter(Screen::Playing), play_soundtrack.after(load_game));
+    app.add_systems(Update, tint_sky.run_if(in_state(Screen::Playing)));
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug, Clone, Default)]
+pub struct CycleDefinitions {
+    /// The run starts in the first cycle.
+    pub cycles: Vec<CycleDefinition>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CycleDefinition {
+    /// Unique name that remembers the cycle in saves.
+    pub id: String,
+    /// Path of the glTF file with the cycle's geometry, relative to the assets folder.
+    pub scene: String,
+    /// The cycle that follows when the day is over. Defaults to the next cycle
+    /// in the list that isn't terminal, starting over at the top after the last one.
+    #[serde(default)]
+    pub next: Option<String>,
+    /// Once a terminal cycle is reached, time no longer moves on to another cycle.
+    /// Finishing the monument leads to the first terminal cycle.
+    #[serde(default)]
+    pub terminal: bool,
+    /// Path of the music that loops during this cycle, relative to the assets folder.
+    #[serde(default)]
+    pub soundtrack: Option<String>,
+    /// Color the sky is multiplied with.
+    #[serde(default = "no_tint")]
+    pub sky_tint: [f32; 3],
+    #[serde(skip)]
+    pub soundtrack_handle: Option<Handle<AudioSource>>,
+}
+
+fn no_tint() -> [f32; 3] {
+    [1.0; 3]
+}
+
+impl CycleDefinition {
+    pub fn cycle(&self) -> Cycle {
+        Cycle(self.id.clone())
+    }
+}
+
+/// The cycle definitions in use.
+#[derive(Resource, Default, Debug)]
+pub struct Cycles(pub CycleDefinitions);
+
+impl Cycles {
+    pub fn get(&self, cycle: &Cycle) -> Option<&CycleDefinition> {
+        self.0
+            .cycles
+            .iter()
+            .find(|definition| definition.id == cycle.0)
+    }
+
+    pub fn first(&self) -> Cycle {
+        self.0
+            .cycles
+            .first()
+            .map(CycleDefinition::cycle)
+            .unwrap_or_default()
+    }
+
+    /// Position of the cycle in the list.
+    pub fn index(&self, cycle: &Cycle) -> Option<usize> {
+        self.0
+            .cycles
+            .iter()
+            .position(|definition| definition.id == cycle.0)
+    }
+
+    pub fn is_terminal(&self, cycle: &Cycle) -> bool {
+        self.get(cycle)
+            .is_some_and(|definition| definition.terminal)
+    }
+
+    /// The cycle that follows the given one when the day is over.
+    pub fn next(&self, cycle: &Cycle) -> Cycle {
+        let Some(index) = self.index(cycle) else {
+            return self.first();
+        };
+        let definition = &self.0.cycles[index];
+        if definition.terminal {
+            return cycle.clone();
+        }
+        if let Some(next) = &definition.next {
+            return Cycle(next.clone());
+        }
+        let count = self.0.cycles.len();
+        (1..=count)
+            .map(|offset| &self.0.cycles[(index + offset) % count])
+            .find(|definition| !definition.terminal)
+            .map_or_else(|| cycle.clone(), CycleDefinition::cycle)
+    }
+
+    /// The cycle that is entered once the monument is finished.
+    pub fn finale(&self) -> Option<Cycle> {
+        self.0
+            .cycles
+            .iter()
+            .find(|definition| definition.terminal)
+            .map(CycleDefinition::cycle)
+    }
+
+    /// The cycle that is entered when the day is over.
+    pub fn after_day(&self, cycle: &Cycle, monument_finished: bool) -> Cycle {
+        self.finale()
+            .filter(|_| monument_finished)
+            .unwrap_or_else(|| self.next(cycle))
+    }
+
+    pub fn soundtrack(&self, cycle: &Cycle) -> Option<Handle<AudioSource>> {
+        self.get(cycle)?.soundtrack_handle.clone()
+    }
+}
+
+impl DataAsset for CycleDefinitions {
+    const EXTENSION: &'static str = "cycles.ron";
+
+    fn validate(&mut self, load_context: &mut LoadContext<'_>) -> Result<(), DataError> {
+        if self.cycles.is_empty() {
+            return Err(DataError::Invalid(
+                "there has to be at least one cycle".into(),
+            ));
+        }
+        let ids = unique_ids(
+            "cycle",
+            self.cycles.iter().map(|definition| definition.id.as_str()),
+        )?;
+        let unknown = self
+            .cycles
+            .iter()
+            .filter_map(|definition| definition.next.as_deref())
+            .find(|next| !ids.contains(next));
+        if let Some(next) = unknown {
+            return Err(DataError::Invalid(format!(
+                "cycle \"{next}\" is referenced but not defined"
+            )));
+        }
+        for definition in &mut self.cycles {
+            // The music is loaded together with the definitions, so it is ready when it's needed.
+            definition.soundtrack_handle = definition
+                .soundtrack
+                .clone()
+                .map(|path| load_context.load(path));
+        }
+        Ok(())
+    }
+}
+
+fn update_cycles(
+    mut events: EventReader<AssetEvent<CycleDefinitions>>,
+    handle: Res<DataHandle<CycleDefinitions>>,
+    definitions: Res<Assets<CycleDefinitions>>,
+    mut cycles: ResMut<Cycles>,
+) {
+    // The handle changes when another level is loaded.
+    let reloaded = events
+        .read()
+        .any(|event| event.is_loaded_with_dependencies(&handle.0) || event.is_modified(&handle.0));
+    if !reloaded && !handle.is_changed() {
+        return;
+    }
+    if let Some(definitions) = definitions.get(&handle.0) {
+        cycles.0 = definitions.clone();
+    }
+}
+
+fn play_soundtrack(mut commands: Commands, cycles: Res<Cycles>, current_cycle: Res<CurrentCycle>) {
+    if let Some(soundtrack) = cycles.soundtrack(&current_cycle.0) {
+        commands.trigger(PlaySoundtrack::Handle(soundtrack));
+    }
+}
+
+fn tint_sky(
+    cycles: Res<Cycles>,
+    current_cycle: Res<CurrentCycle>,
+    sky_materials: Query<&Handle<SkyMaterial>>,
+    new_sky_materials: Query<(), Added<Handle<SkyMaterial>>>,
+    mut materials: ResMut<Assets<SkyMaterial>>,
+) {
+    if !cycles.is_changed() && !current_cycle.is_changed() && new_sky_materials.is_empty() {
+        return;
+    }
+    let tint = cycles
+        .get(&current_cycle.0)
+        .map_or(no_tint(), |definition| definition.sky_tint);
+    for material in &sky_materials {
+        if let Some(sky) = materials.get_mut(material) {
+            sky.tint = Vec3::from_array(tint).extend(1.0);
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Data files in RON that are loaded as assets, like the cycles or items of a level. Each kind
+//! of file gets a [`RonLoader`] and a [`DataHandle`] through [`init_data_file`], and only has to
+//! describe how its contents are checked.
+
+use std::{error::Error, fmt, io, marker::PhantomData};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::HashSet,
+};
+use serde::de::DeserializeOwned;
+
+use super::levels::CurrentLevel;
+
+/// Load the data files of type `T`, starting with the one of the current level.
+pub fn init_data_file<T: DataAsset>(app: &mut App) {
+    app.init_asset::<T>();
+    app.register_asset_loader(RonLoader::<T>::default());
+    app.init_resource::<DataHandle<T>>();
+}
+
+pub trait DataAsset: Asset + DeserializeOwned {
+    /// Extension of the files, e.g. `"cycles.ron"` for `data/island.cycles.ron`.
+    const EXTENSION: &'static str;
+
+    /// Path of the file to load for a level.
+    fn path(level: &CurrentLevel) -> String {
+        level.data_file(Self::EXTENSION)
+    }
+
+    /// Check the contents once they are read, and start loading the assets they refer to.
+    fn validate(&mut self, _load_context: &mut LoadContext<'_>) -> Result<(), DataError> {
+        Ok(())
+    }
+}
+
+/// Handle to a data file, kept alive for the whole game so it can be hot reloaded.
+#[derive(Resource)]
+pub struct DataHandle<T: DataAsset>(pub Handle<T>);
+
+impl<T: DataAsset> DataHandle<T> {
+    pub fn load(asset_server: &AssetServer, level: &CurrentLevel) -> Self {
+        Self(asset_server.load(T::path(level)))
+    }
+}
+
+impl<T: DataAsset> FromWorld for DataHandle<T> {
+    fn from_world(world: &mut World) -> Self {
+        world.init_resource::<CurrentLevel>();
+        Self::load(
+            world.resource::<AssetServer>(),
+            world.resource::<CurrentLevel>(),
+        )
+    }
+}
+
+pub struct RonLoader<T> {
+    extensions: [&'static str; 1],
+    asset: PhantomData<fn() -> T>,
+}
+
+impl<T: DataAsset> Default for RonLoader<T> {
+    fn default() -> Self {
+        Self {
+            extensions: [T::EXTENSION],
+            asset: PhantomData,
+        }
+    }
+}
+
+impl<T: DataAsset> AssetLoader for RonLoader<T> {
+    type Asset = T;
+    type Settings = ();
+    type Error = DataError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut data: T = ron::de::from_bytes(&bytes)?;
+        data.validate(load_context)?;
+        Ok(data)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+}
+
+#[derive(Debug)]
+pub enum DataError {
+    Io(io::Error),
+    Deserialize(ron::error::SpannedError),
+    /// The file could be read, but its contents don't fit together.
+    Invalid(String),
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataError::Io(error) => write!(f, "{error}"),
+            DataError::Deserialize(error) => write!(f, "{error}"),
+            DataError::Invalid(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl Error for DataError {}
+
+impl From<io::Error> for DataError {
+    fn from(error: io::Error) -> Self {
+        DataError::Io(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for DataError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        DataError::Deserialize(error)
+    }
+}
+
+/// Check that no id is defined twice and return the ids. `kind` names what they identify.
+pub fn unique_ids<'a>(
+    kind: &str,
+    ids: impl IntoIterator<Item = &'a str>,
+) -> Result<HashSet<&'a str>, DataError> {
+    let mut unique = HashSet::new();
+    for id in ids {
+        if !unique.insert(id) {
+            return Err(DataError::Invalid(format!(
+                "{kind} \"{id}\" is defined more than once"
+            )));
+        }
+    }
+    Ok(unique)
+}
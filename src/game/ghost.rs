@@ -0,0 +1,243 @@
+//! A translucent ghost that retraces the best finished run alongside the player.
+//! The track of the current run is part of its [`RunStats`] and saved next to the save slot.
+
+use std::{fs, io, path::PathBuf};
+
+use bevy::{input::common_conditions::input_just_pressed, pbr::NotShadowCaster, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    screen::{PlayState, Screen},
+    AppSet,
+};
+
+use super::{
+    levels::CurrentLevel,
+    logic::{CurrentCycle, Cycle},
+    records::{record_run, LastRun, RunRecord, RunStats},
+    save::{data_dir, DisableSaving, SaveError},
+    spawn::player::Player,
+};
+
+/// Bump this whenever [`GhostRun`] changes in a way that old files can't be read anymore.
+pub const GHOST_VERSION: u32 = 2;
+
+/// Seconds of play time between two recorded positions.
+const SAMPLE_INTERVAL: f32 = 0.2;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<BestGhost>();
+    app.add_systems(OnEnter(Screen::Playing), (load_ghost, spawn_ghost).chain());
+    app.add_systems(OnEnter(Screen::Credits), save_ghost.after(record_run));
+    app.add_systems(
+        Update,
+        record_track
+            .in_set(AppSet::Update)
+            .run_if(in_state(PlayState::InGame)),
+    );
+    app.add_systems(
+        Update,
+        (
+            toggle_ghost.run_if(input_just_pressed(KeyCode::KeyG)),
+            update_ghost,
+        )
+            .chain()
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+/// Where the player went during a run.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct GhostTrack {
+    /// Player positions, one every [`SAMPLE_INTERVAL`] seconds of play time.
+    positions: Vec<[f32; 3]>,
+    /// Play time at which each cycle was entered.
+    cycles: Vec<(f32, Cycle)>,
+}
+
+impl GhostTrack {
+    /// Position at the given play time, or `None` if the run was already over.
+    pub fn position(&self, time: f32) -> Option<Vec3> {
+        let index = time.max(0.0) / SAMPLE_INTERVAL;
+        let (before, after) = (index.floor() as usize, index.ceil() as usize);
+        let before = Vec3::from_array(*self.positions.get(before)?);
+        let after = Vec3::from_array(*self.positions.get(after)?);
+        Some(before.lerp(after, index.fract()))
+    }
+
+    /// Cycle at the given play time.
+    pub fn cycle(&self, time: f32) -> Option<&Cycle> {
+        self.cycles
+            .iter()
+            .take_while(|(start, _)| *start <= time)
+            .last()
+            .map(|(_, cycle)| cycle)
+    }
+}
+
+/// A finished run and its track, as stored on disk.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GhostRun {
+    pub version: u32,
+    pub record: RunRecord,
+    pub track: GhostTrack,
+}
+
+impl GhostRun {
+    /// The best run of the given level, if there is one.
+    pub fn load(level: &str) -> Option<Self> {
+        match Self::read(level) {
+            // Don't race against a run of another level, e.g. after its file was copied over.
+            Ok(ghost) if ghost.record.level != level => {
+                warn!("Ignoring ghost of level \"{}\"", ghost.record.level);
+                None
+            }
+            Ok(ghost) => Some(ghost),
+            Err(SaveError::Io(error)) if error.kind() == io::ErrorKind::NotFound => None,
+            Err(error) => {
+                warn!("Ignoring ghost file: {error}");
+                None
+            }
+        }
+    }
+
+    fn read(level: &str) -> Result<Self, SaveError> {
+        let contents = fs::read_to_string(ghost_path(level))?;
+        let ghost: GhostRun = ron::from_str(&contents)?;
+        if ghost.version != GHOST_VERSION {
+            return Err(SaveError::UnsupportedVersion(ghost.version));
+        }
+        Ok(ghost)
+    }
+
+    pub fn write(&self) -> Result<(), SaveError> {
+        fs::create_dir_all(data_dir())?;
+        let contents = ron::to_string(self)?;
+        fs::write(ghost_path(&self.record.level), contents)?;
+        Ok(())
+    }
+}
+
+fn ghost_path(level: &str) -> PathBuf {
+    data_dir().join(format!("ghost_{level}.ron"))
+}
+
+#[derive(Resource, Default)]
+pub struct BestGhost {
+    pub visible: bool,
+    pub run: Option<GhostRun>,
+}
+
+#[derive(Component)]
+struct Ghost;
+
+fn record_track(
+    mut stats: ResMut<RunStats>,
+    current_cycle: Res<CurrentCycle>,
+    player: Query<&GlobalTransform, With<Player>>,
+) {
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let time = stats.play_time;
+    let track = &mut stats.track;
+    if track.cycles.last().map(|(_, cycle)| cycle) != Some(&current_cycle.0) {
+        track.cycles.push((time, current_cycle.0.clone()));
+    }
+    while track.positions.len() as f32 * SAMPLE_INTERVAL <= time {
+        track.positions.push(player.translation().to_array());
+    }
+}
+
+fn load_ghost(mut ghost: ResMut<BestGhost>, current_level: Res<CurrentLevel>) {
+    ghost.run = GhostRun::load(&current_level.0);
+}
+
+fn spawn_ghost(
+    mut commands: Commands,
+    ghost: Res<BestGhost>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if ghost.run.is_none() {
+        return;
+    }
+    commands.spawn((
+        Name::new("Ghost"),
+        Ghost,
+        StateScoped(Screen::Playing),
+        NotShadowCaster,
+        PbrBundle {
+            // Same size as the player's collider.
+            mesh: meshes.add(Capsule3d::new(0.3, 1.0)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::srgba(0.8, 0.9, 1.0, 0.3),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            }),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+    ));
+}
+
+fn toggle_ghost(mut ghost: ResMut<BestGhost>) {
+    ghost.visible = !ghost.visible;
+}
+
+fn update_ghost(
+    ghost: Res<BestGhost>,
+    stats: Res<RunStats>,
+    current_cycle: Res<CurrentCycle>,
+    mut ghost_query: Query<(&mut Transform, &mut Visibility), With<Ghost>>,
+) {
+    let (Some(run), Ok((mut transform, mut visibility))) =
+        (&ghost.run, ghost_query.get_single_mut())
+    else {
+        return;
+    };
+    let time = stats.play_time;
+    // The geometry of other cycles isn't spawned, so the ghost would float around in thin air.
+    let position = run
+        .track
+        .position(time)
+        .filter(|_| run.track.cycle(time) == Some(&current_cycle.0));
+    *visibility = match (ghost.visible, position) {
+        (true, Some(position)) => {
+            transform.translation = position;
+            Visibility::Inherited
+        }
+        _ => Visibility::Hidden,
+    };
+}
+
+/// Keep the track of the run that was just finished if it beat the previous ghost.
+fn save_ghost(
+    mut ghost: ResMut<BestGhost>,
+    last_run: Option<Res<LastRun>>,
+    stats: Res<RunStats>,
+    disable_saving: Option<Res<DisableSaving>>,
+) {
+    let Some(last_run) = last_run else {
+        return;
+    };
+    if disable_saving.is_some() {
+        return;
+    }
+    let is_better = ghost.run.as_ref().map_or(true, |best| {
+        last_run.record.compare(&best.record) == std::cmp::Ordering::Less
+    });
+    if !is_better {
+        return;
+    }
+    let run = GhostRun {
+        version: GHOST_VERSION,
+        record: last_run.record.clone(),
+        track: stats.track.clone(),
+    };
+    if let Err(error) = run.write() {
+        warn!("Could not save ghost: {error}");
+    }
+    ghost.run = Some(run);
+}
@@ -0,0 +1,233 @@
+//! The hourglass bends time: holding Q fast-forwards the day, holding R rewinds it toward dawn and
+//! carries the player back along the way they came. Rewinding drains a charge that refills
+//! slowly, and once it is empty the hourglass has to recharge fully before it rewinds again.
+
+use bevy::{prelude::*, ui::Val::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    screen::{PlayState, Screen},
+    ui::prelude::*,
+    AppSet,
+};
+
+use super::{
+    clock::{advance_clock, ClockSource, WorldClock},
+    input::PlayerInput,
+    inventory::{Inventory, ItemAction, Items},
+    logic::{BoatPosition, CycleChanged},
+    records::RunStats,
+    save::load_game,
+    spawn::player::Player,
+};
+
+/// How much faster time passes while fast-forwarding.
+const FAST_FORWARD_SCALE: f32 = 30.0;
+/// How much faster time runs backwards while rewinding.
+const REWIND_SCALE: f32 = 10.0;
+/// Real time a full charge lasts while rewinding, in seconds.
+const REWIND_DURATION: f32 = 4.0;
+/// Real time an empty charge takes to refill, in seconds.
+const RECHARGE_DURATION: f32 = 30.0;
+/// Day progress between two recorded player positions.
+const RECORD_INTERVAL: f32 = 0.002;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<RewindCharge>();
+    app.init_resource::<PositionHistory>();
+    app.observe(forget_positions);
+    app.add_systems(
+        OnEnter(Screen::Playing),
+        (forget_all_positions.after(load_game), spawn_charge_meter),
+    );
+    app.add_systems(
+        Update,
+        (
+            use_hourglass.before(advance_clock),
+            (record_positions, rewind_positions).after(advance_clock),
+        )
+            .in_set(AppSet::TickTimers)
+            .run_if(in_state(PlayState::InGame)),
+    );
+    app.add_systems(
+        Update,
+        update_charge_meter.run_if(in_state(Screen::Playing)),
+    );
+}
+
+/// How much rewinding the hourglass has left. It is saved with the run.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+pub struct RewindCharge {
+    /// From 0 when empty to 1 when full.
+    pub charge: f32,
+    /// Set when the charge runs out, until it is full again.
+    pub exhausted: bool,
+}
+
+impl Default for RewindCharge {
+    fn default() -> Self {
+        Self {
+            charge: 1.0,
+            exhausted: false,
+        }
+    }
+}
+
+/// Where the player was earlier in the day, oldest first.
+#[derive(Resource, Default, Debug)]
+struct PositionHistory(Vec<(f32, Vec3)>);
+
+/// The charge is reset or restored by `load_game`, only the positions start over.
+fn forget_all_positions(mut history: ResMut<PositionHistory>) {
+    history.0.clear();
+}
+
+/// Time can't be rewound into the previous cycle, so neither can the player.
+fn forget_positions(_trigger: Trigger<CycleChanged>, mut history: ResMut<PositionHistory>) {
+    history.0.clear();
+}
+
+fn use_hourglass(
+    input: Res<PlayerInput>,
+    inventory: Res<Inventory>,
+    items: Res<Items>,
+    time: Res<Time>,
+    mut clock: ResMut<WorldClock>,
+    mut charge: ResMut<RewindCharge>,
+    mut stats: ResMut<RunStats>,
+) {
+    let rewind = input.rewind
+        && inventory.can(ItemAction::Rewind, &items)
+        && !charge.exhausted
+        && clock.progress > 0.0;
+    let fast_forward = input.fast_forward && inventory.can(ItemAction::FastForward, &items);
+    match (rewind, fast_forward) {
+        (true, _) => clock.set_scale(ClockSource::Hourglass, -REWIND_SCALE),
+        (false, true) => clock.set_scale(ClockSource::Hourglass, FAST_FORWARD_SCALE),
+        (false, false) => clock.clear_scale(ClockSource::Hourglass),
+    }
+
+    let delta = time.delta_seconds();
+    if rewind && !clock.is_paused() {
+        charge.charge -= delta / REWIND_DURATION;
+        if charge.charge <= 0.0 {
+            charge.charge = 0.0;
+            charge.exhausted = true;
+        }
+    } else if !rewind {
+        charge.charge = (charge.charge + delta / RECHARGE_DURATION).min(1.0);
+        if charge.charge >= 1.0 {
+            charge.exhausted = false;
+        }
+    }
+    // Bending time either way makes for a run that wasn't played at normal speed.
+    if (fast_forward || rewind) && !clock.is_paused() {
+        stats.hourglass_time += delta;
+    }
+}
+
+fn record_positions(
+    clock: Res<WorldClock>,
+    boat_position: Res<BoatPosition>,
+    player: Query<&Transform, With<Player>>,
+    mut history: ResMut<PositionHistory>,
+) {
+    // While rowing, the player's transform is relative to the boat.
+    if clock.scale() <= 0.0 || boat_position.currently_rowing {
+        return;
+    }
+    let Ok(player) = player.get_single() else {
+        return;
+    };
+    let due = history.0.last().map_or(true, |(progress, _)| {
+        clock.progress - progress >= RECORD_INTERVAL
+    });
+    if due {
+        history.0.push((clock.progress, player.translation));
+    }
+}
+
+fn rewind_positions(
+    clock: Res<WorldClock>,
+    mut player: Query<&mut Transform, With<Player>>,
+    mut history: ResMut<PositionHistory>,
+) {
+    if clock.scale() >= 0.0 {
+        return;
+    }
+    let Ok(mut player) = player.get_single_mut() else {
+        return;
+    };
+    let mut restored = None;
+    while let Some(&(progress, translation)) = history.0.last() {
+        if progress <= clock.progress {
+            break;
+        }
+        restored = Some(translation);
+        history.0.pop();
+    }
+    if let Some(translation) = restored {
+        player.translation = translation;
+    }
+}
+
+#[derive(Component)]
+struct ChargeMeter;
+
+#[derive(Component)]
+struct ChargeMeterFill;
+
+fn spawn_charge_meter(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Px(80.0),
+                left: Percent(45.0),
+                width: Percent(10.0),
+                height: Px(6.0),
+                ..default()
+            },
+            background_color: BackgroundColor(ui_palette::NODE_BACKGROUND),
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .insert((Name::new("Rewind charge"), StateScoped(Screen::Playing)))
+        .insert(ChargeMeter)
+        .with_children(|meter| {
+            meter
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Percent(100.0),
+                        height: Percent(100.0),
+                        ..default()
+                    },
+                    background_color: BackgroundColor(ui_palette::LABEL_TEXT),
+                    ..default()
+                })
+                .insert(ChargeMeterFill);
+        });
+}
+
+fn update_charge_meter(
+    charge: Res<RewindCharge>,
+    inventory: Res<Inventory>,
+    items: Res<Items>,
+    mut meter: Query<&mut Visibility, With<ChargeMeter>>,
+    mut fill: Query<(&mut Style, &mut BackgroundColor), With<ChargeMeterFill>>,
+) {
+    for mut visibility in &mut meter {
+        *visibility = match inventory.can(ItemAction::Rewind, &items) {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        };
+    }
+    for (mut style, mut color) in &mut fill {
+        style.width = Percent(charge.charge * 100.0);
+        // An exhausted hourglass is greyed out until it is full again.
+        color.0 = match charge.exhausted {
+            true => ui_palette::BUTTON_HOVERED_BACKGROUND,
+            false => ui_palette::LABEL_TEXT,
+        };
+    }
+}
@@ -0,0 +1,437 @@
+//! Gather player input into [`PlayerInput`], either from the keyboard and mouse or from a recording.
+//!
+//! Start the game with `--record <file>` to record everything that happens while playing,
+//! and with `--replay <file>` to play it back. A recording starts from the save the run was
+//! loaded from and also contains the length of every frame and when the game was paused,
+//! so a replay steps through exactly the same frames as the original run.
+
+use std::{
+    env,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use bevy::{
+    input::{mouse::MouseMotion, InputSystem},
+    prelude::*,
+    time::TimeUpdateStrategy,
+};
+
+use crate::{
+    screen::{PlayState, Screen},
+    AppSet,
+};
+
+use super::{
+    levels::CurrentLevel,
+    save::{load_game, read_slot, ActiveSlot, DisableSaving, LoadFrom, SaveData, SaveError},
+};
+
+/// Identifies recording files, followed by the format version.
+const RECORDING_MAGIC: &[u8; 4] = b"WLRC";
+const RECORDING_VERSION: u8 = 1;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<PlayerInput>();
+    app.init_resource::<InputSource>();
+    app.add_systems(Startup, parse_arguments);
+    app.add_systems(
+        PreUpdate,
+        (
+            read_devices.run_if(not(replaying)),
+            play_back.run_if(replaying),
+            block_input.run_if(resource_exists::<BlockInput>),
+        )
+            .chain()
+            .after(InputSystem)
+            .before(AppSet::RecordInput)
+            .run_if(in_state(Screen::Playing)),
+    );
+    app.add_systems(
+        OnEnter(Screen::Playing),
+        (start_recording, start_replay).before(load_game),
+    );
+    app.add_systems(OnEnter(PlayState::InMenu), write_recording);
+    app.add_systems(OnExit(Screen::Playing), write_recording);
+    app.add_systems(Last, write_recording.run_if(on_event::<AppExit>()));
+}
+
+/// Input the game reacts to. Systems should read this instead of the keyboard and mouse,
+/// so that their behavior can be recorded and replayed.
+#[derive(Resource, Default, Debug)]
+pub struct PlayerInput {
+    /// Movement intent, each axis is -1, 0 or 1.
+    pub movement: Vec2,
+    pub jump: bool,
+    /// Mouse motion that hasn't been applied to the camera yet.
+    pub look: Vec2,
+    /// Whether interact was pressed this frame.
+    pub interact: bool,
+    /// Whether time should be fast-forwarded with the hourglass.
+    pub fast_forward: bool,
+    /// Whether time should be rewound with the hourglass.
+    pub rewind: bool,
+}
+
+/// While this resource exists, all input is ignored, e.g. while the comet strikes.
+#[derive(Resource, Debug)]
+pub struct BlockInput;
+
+impl PlayerInput {
+    fn apply(&mut self, frame: &InputFrame) {
+        self.movement = frame.movement;
+        self.jump = frame.jump;
+        self.look += frame.look;
+        self.interact = frame.interact;
+        self.fast_forward = frame.fast_forward;
+        self.rewind = frame.rewind;
+    }
+}
+
+/// Everything that is recorded about a single frame.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct InputFrame {
+    pub delta: Duration,
+    /// Whether the game was running rather than paused at the start of the frame.
+    pub in_game: bool,
+    pub movement: Vec2,
+    pub jump: bool,
+    pub look: Vec2,
+    pub interact: bool,
+    pub fast_forward: bool,
+    pub rewind: bool,
+}
+
+impl InputFrame {
+    const RIGHT: u16 = 1 << 0;
+    const LEFT: u16 = 1 << 1;
+    const FORWARD: u16 = 1 << 2;
+    const BACK: u16 = 1 << 3;
+    const JUMP: u16 = 1 << 4;
+    const INTERACT: u16 = 1 << 5;
+    const FAST_FORWARD: u16 = 1 << 6;
+    const IN_GAME: u16 = 1 << 7;
+    const LOOK: u16 = 1 << 8;
+    const REWIND: u16 = 1 << 9;
+
+    /// Frames are stored as the delta in nanoseconds and a set of flags,
+    /// followed by the mouse motion if there was any.
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        let flag = |condition: bool, bit: u16| if condition { bit } else { 0 };
+        let flags = flag(self.movement.x > 0.0, Self::RIGHT)
+            | flag(self.movement.x < 0.0, Self::LEFT)
+            | flag(self.movement.y > 0.0, Self::FORWARD)
+            | flag(self.movement.y < 0.0, Self::BACK)
+            | flag(self.jump, Self::JUMP)
+            | flag(self.interact, Self::INTERACT)
+            | flag(self.fast_forward, Self::FAST_FORWARD)
+            | flag(self.in_game, Self::IN_GAME)
+            | flag(self.look != Vec2::ZERO, Self::LOOK)
+            | flag(self.rewind, Self::REWIND);
+        let nanos = u32::try_from(self.delta.as_nanos()).unwrap_or(u32::MAX);
+        bytes.extend_from_slice(&nanos.to_le_bytes());
+        bytes.extend_from_slice(&flags.to_le_bytes());
+        if flags & Self::LOOK != 0 {
+            bytes.extend_from_slice(&self.look.x.to_le_bytes());
+            bytes.extend_from_slice(&self.look.y.to_le_bytes());
+        }
+    }
+
+    fn decode(bytes: &mut &[u8]) -> Option<Self> {
+        let nanos = u32::from_le_bytes(take(bytes)?);
+        let flags = u16::from_le_bytes(take(bytes)?);
+        let look = match flags & Self::LOOK != 0 {
+            true => Vec2::new(
+                f32::from_le_bytes(take(bytes)?),
+                f32::from_le_bytes(take(bytes)?),
+            ),
+            false => Vec2::ZERO,
+        };
+        let axis = |positive: u16, negative: u16| {
+            (flags & positive != 0) as i8 as f32 - (flags & negative != 0) as i8 as f32
+        };
+        Some(Self {
+            delta: Duration::from_nanos(nanos.into()),
+            in_game: flags & Self::IN_GAME != 0,
+            movement: Vec2::new(
+                axis(Self::RIGHT, Self::LEFT),
+                axis(Self::FORWARD, Self::BACK),
+            ),
+            jump: flags & Self::JUMP != 0,
+            look,
+            interact: flags & Self::INTERACT != 0,
+            fast_forward: flags & Self::FAST_FORWARD != 0,
+            rewind: flags & Self::REWIND != 0,
+        })
+    }
+}
+
+fn take<const N: usize>(bytes: &mut &[u8]) -> Option<[u8; N]> {
+    let (taken, rest) = bytes.split_first_chunk::<N>()?;
+    *bytes = rest;
+    Some(*taken)
+}
+
+/// A recorded run.
+#[derive(Default, Debug)]
+pub struct Recording {
+    /// The save the run was started from, if any.
+    pub start: Option<SaveData>,
+    pub frames: Vec<InputFrame>,
+}
+
+impl Recording {
+    pub fn read(path: &Path) -> Result<Self, SaveError> {
+        let mut bytes = vec![];
+        BufReader::new(File::open(path)?).read_to_end(&mut bytes)?;
+        let mut bytes = bytes.as_slice();
+
+        if take::<4>(&mut bytes).as_ref() != Some(RECORDING_MAGIC) {
+            return Err(SaveError::Malformed("not a recording"));
+        }
+        let [version] = take(&mut bytes).ok_or(SaveError::Malformed("missing version"))?;
+        if version != RECORDING_VERSION {
+            return Err(SaveError::Malformed("unsupported recording version"));
+        }
+        let start_length = take(&mut bytes)
+            .map(u32::from_le_bytes)
+            .ok_or(SaveError::Malformed("missing start"))?;
+        if bytes.len() < start_length as usize {
+            return Err(SaveError::Malformed("truncated start"));
+        }
+        let (start, mut bytes) = bytes.split_at(start_length as usize);
+        let start =
+            std::str::from_utf8(start).map_err(|_| SaveError::Malformed("invalid start"))?;
+        let start = ron::from_str(start)?;
+
+        let mut frames = vec![];
+        while !bytes.is_empty() {
+            frames.push(
+                InputFrame::decode(&mut bytes).ok_or(SaveError::Malformed("truncated frame"))?,
+            );
+        }
+        Ok(Self { start, frames })
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), SaveError> {
+        let start = ron::to_string(&self.start)?;
+        let mut bytes = Vec::with_capacity(start.len() + self.frames.len() * 8);
+        bytes.extend_from_slice(RECORDING_MAGIC);
+        bytes.push(RECORDING_VERSION);
+        bytes.extend_from_slice(&(start.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(start.as_bytes());
+        for frame in &self.frames {
+            frame.encode(&mut bytes);
+        }
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(&bytes)?;
+        file.flush()?;
+        Ok(())
+    }
+}
+
+/// Where [`PlayerInput`] comes from.
+#[derive(Resource, Default)]
+pub enum InputSource {
+    #[default]
+    Devices,
+    Replay {
+        frames: Vec<InputFrame>,
+        next: usize,
+    },
+}
+
+/// Run condition that is true while a recording is being replayed.
+pub fn replaying(source: Res<InputSource>) -> bool {
+    matches!(*source, InputSource::Replay { .. })
+}
+
+/// Records every frame of the current run while it exists.
+#[derive(Resource)]
+struct Recorder {
+    path: PathBuf,
+    recording: Recording,
+}
+
+fn parse_arguments(mut commands: Commands) {
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let path = match arg.as_str() {
+            "--record" | "--replay" => args.next(),
+            _ => {
+                warn!("Ignoring unknown argument {arg}");
+                continue;
+            }
+        };
+        let Some(path) = path else {
+            warn!("Missing file name after {arg}");
+            continue;
+        };
+        if arg == "--record" {
+            info!("Recording to {path}");
+            commands.insert_resource(Recorder {
+                path: path.into(),
+                recording: default(),
+            });
+            continue;
+        }
+        match Recording::read(Path::new(&path)) {
+            Ok(recording) => {
+                info!("Replaying {path}");
+                // Playing back a run must not overwrite the player's progress.
+                commands.insert_resource(DisableSaving);
+                if let Some(start) = &recording.start {
+                    commands.insert_resource(CurrentLevel(start.level.clone()));
+                }
+                commands.insert_resource(LoadFrom(recording.start));
+                commands.insert_resource(InputSource::Replay {
+                    frames: recording.frames,
+                    next: 0,
+                });
+            }
+            Err(error) => warn!("Could not read recording {path}: {error}"),
+        }
+    }
+}
+
+fn block_input(mut input: ResMut<PlayerInput>) {
+    *input = PlayerInput::default();
+}
+
+fn read_devices(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    time: Res<Time>,
+    play_state: Res<State<PlayState>>,
+    mut input: ResMut<PlayerInput>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    recorder: Option<ResMut<Recorder>>,
+) {
+    let look: Vec2 = mouse_motion.read().map(|motion| motion.delta).sum();
+    let mut frame = InputFrame {
+        delta: time.delta(),
+        in_game: *play_state.get() == PlayState::InGame,
+        ..default()
+    };
+    // Input in menus doesn't reach the game.
+    if frame.in_game {
+        frame.movement = Vec2::new(
+            keys.pressed(KeyCode::KeyD) as i8 as f32 - keys.pressed(KeyCode::KeyA) as i8 as f32,
+            keys.pressed(KeyCode::KeyW) as i8 as f32 - keys.pressed(KeyCode::KeyS) as i8 as f32,
+        );
+        frame.jump = keys.pressed(KeyCode::Space);
+        frame.look = look;
+        frame.interact = keys.just_pressed(KeyCode::KeyE);
+        frame.fast_forward = keys.pressed(KeyCode::KeyQ);
+        frame.rewind = keys.pressed(KeyCode::KeyR);
+    }
+
+    input.apply(&frame);
+    if let Some(mut recorder) = recorder {
+        if recorder.recording.frames.is_empty() {
+            discard_overstep(&mut fixed_time);
+        }
+        recorder.recording.frames.push(frame);
+    }
+}
+
+fn play_back(
+    mut source: ResMut<InputSource>,
+    mut input: ResMut<PlayerInput>,
+    mut time_strategy: ResMut<TimeUpdateStrategy>,
+    mut next_play_state: ResMut<NextState<PlayState>>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    recorder: Option<ResMut<Recorder>>,
+) {
+    let InputSource::Replay { frames, next } = &mut *source else {
+        return;
+    };
+    let Some(&frame) = frames.get(*next) else {
+        return;
+    };
+    if *next == 0 {
+        discard_overstep(&mut fixed_time);
+    }
+    *next += 1;
+    input.apply(&frame);
+    if let Some(mut recorder) = recorder {
+        recorder.recording.frames.push(frame);
+    }
+
+    // Prepare the next frame the way it was recorded.
+    match frames.get(*next) {
+        Some(next_frame) => {
+            *time_strategy = TimeUpdateStrategy::ManualDuration(next_frame.delta);
+            if next_frame.in_game != frame.in_game {
+                next_play_state.set(match next_frame.in_game {
+                    true => PlayState::InGame,
+                    false => PlayState::InMenu,
+                });
+            }
+        }
+        None => {
+            info!("Replay finished");
+            *time_strategy = TimeUpdateStrategy::Automatic;
+            *source = InputSource::Devices;
+        }
+    }
+}
+
+/// Time left over from before the run would otherwise shift when fixed updates happen.
+fn discard_overstep(fixed_time: &mut Time<Fixed>) {
+    let overstep = fixed_time.overstep();
+    fixed_time.discard_overstep(overstep);
+}
+
+fn start_recording(
+    recorder: Option<ResMut<Recorder>>,
+    active_slot: Res<ActiveSlot>,
+    load_from: Option<Res<LoadFrom>>,
+) {
+    let Some(mut recorder) = recorder else {
+        return;
+    };
+    // Every time the world is loaded, a new run starts.
+    recorder.recording = Recording {
+        start: match load_from {
+            Some(load_from) => load_from.0.clone(),
+            None => read_slot(active_slot.0),
+        },
+        frames: vec![],
+    };
+}
+
+fn start_replay(
+    source: Res<InputSource>,
+    mut time_strategy: ResMut<TimeUpdateStrategy>,
+    mut next_play_state: ResMut<NextState<PlayState>>,
+) {
+    let InputSource::Replay { frames, next: 0 } = &*source else {
+        return;
+    };
+    let Some(first_frame) = frames.first() else {
+        return;
+    };
+    *time_strategy = TimeUpdateStrategy::ManualDuration(first_frame.delta);
+    if first_frame.in_game {
+        next_play_state.set(PlayState::InGame);
+    }
+}
+
+fn write_recording(recorder: Option<Res<Recorder>>) {
+    let Some(recorder) = recorder else {
+        return;
+    };
+    // Don't replace a previous run's recording with an empty one after reloading the world.
+    if recorder.recording.frames.is_empty() {
+        return;
+    }
+    if let Err(error) = recorder.recording.write(&recorder.path) {
+        warn!(
+            "Could not write recording to {}: {error}",
+            recorder.path.display()
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! Items the player can carry. Which items exist is defined in `assets/data/island.items.ron`,
+//! the [`Inventory`] only holds their ids and counts. Progression steps hand items out and take
+//! them away again (see [`crate::game::progression`]).
+
+use bevy::{asset::LoadContext, prelude::*, ui::Val::*, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{screen::Screen, ui::prelude::*};
+
+use super::{
+    data::{init_data_file, unique_ids, DataAsset, DataError, DataHandle},
+    save::load_game,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    init_data_file::<ItemDefinitions>(app);
+    app.init_resource::<Items>();
+    app.init_resource::<Inventory>();
+    app.init_resource::<AnnouncedInventory>();
+    app.add_systems(PreUpdate, update_items);
+    app.add_systems(
+        OnEnter(Screen::Playing),
+        (reset_announced.after(load_game), spawn_inventory_strip),
+    );
+    app.add_systems(
+        Update,
+        (announce_changes, update_inventory_strip).run_if(in_state(Screen::Playing)),
+    );
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug, Clone, Default)]
+pub struct ItemDefinitions {
+    pub items: Vec<ItemDefinition>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ItemDefinition {
+    /// Unique name that remembers the item in saves.
+    pub id: String,
+    /// Name shown to the player.
+    pub name: String,
+    /// Path of the image shown in the HUD, relative to the assets folder.
+    #[serde(default)]
+    pub icon: Option<String>,
+    /// Key items don't stack and are listed first.
+    #[serde(default)]
+    pub key_item: bool,
+    /// What carrying the item lets the player do.
+    #[serde(default)]
+    pub actions: Vec<ItemAction>,
+    #[serde(skip)]
+    pub icon_handle: Option<Handle<Image>>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemAction {
+    /// Hold the fast-forward key to make time pass faster.
+    FastForward,
+    /// Hold the rewind key to turn time back toward dawn.
+    Rewind,
+}
+
+/// The item definitions in use.
+#[derive(Resource, Default, Debug)]
+pub struct Items(pub ItemDefinitions);
+
+impl Items {
+    pub fn get(&self, id: &str) -> Option<&ItemDefinition> {
+        self.0.items.iter().find(|item| item.id == id)
+    }
+
+    fn is_key_item(&self, id: &str) -> bool {
+        self.get(id).is_some_and(|item| item.key_item)
+    }
+}
+
+/// One kind of item and how many of it are carried.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ItemStack {
+    pub item: String,
+    pub count: u32,
+}
+
+/// What the player is carrying.
+#[derive(Resource, Default, Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct Inventory {
+    stacks: Vec<ItemStack>,
+}
+
+impl Inventory {
+    pub fn stacks(&self) -> &[ItemStack] {
+        &self.stacks
+    }
+
+    pub fn count(&self, item: &str) -> u32 {
+        self.stacks
+            .iter()
+            .find(|stack| stack.item == item)
+            .map_or(0, |stack| stack.count)
+    }
+
+    pub fn has(&self, item: &str) -> bool {
+        self.count(item) > 0
+    }
+
+    /// Whether any carried item allows the given action.
+    pub fn can(&self, action: ItemAction, items: &Items) -> bool {
+        self.stacks.iter().any(|stack| {
+            items
+                .get(&stack.item)
+                .is_some_and(|item| item.actions.contains(&action))
+        })
+    }
+
+    /// Add items to the inventory. Key items are only ever carried once.
+    pub fn add(&mut self, item: &str, count: u32, items: &Items) {
+        let limit = match items.is_key_item(item) {
+            true => 1,
+            false => u32::MAX,
+        };
+        match self.stacks.iter_mut().find(|stack| stack.item == item) {
+            Some(stack) => stack.count = stack.count.saturating_add(count).min(limit),
+            None if count > 0 => self.stacks.push(ItemStack {
+                item: item.into(),
+                count: count.min(limit),
+            }),
+            None => {}
+        }
+    }
+
+    /// Remove up to `count` items, returning how many were removed.
+    pub fn remove(&mut self, item: &str, count: u32) -> u32 {
+        let Some(index) = self.stacks.iter().position(|stack| stack.item == item) else {
+            return 0;
+        };
+        let removed = count.min(self.stacks[index].count);
+        self.stacks[index].count -= removed;
+        if self.stacks[index].count == 0 {
+            self.stacks.remove(index);
+        }
+        removed
+    }
+}
+
+/// Triggered whenever the number of carried items of a kind changes.
+#[derive(Event, Debug)]
+pub struct InventoryChanged {
+    pub item: String,
+    pub previous: u32,
+    pub count: u32,
+}
+
+impl DataAsset for ItemDefinitions {
+    const EXTENSION: &'static str = "items.ron";
+
+    fn validate(&mut self, load_context: &mut LoadContext<'_>) -> Result<(), DataError> {
+        unique_ids("item", self.items.iter().map(|item| item.id.as_str()))?;
+        for item in &mut self.items {
+            item.icon_handle = item.icon.clone().map(|path| load_context.load(path));
+        }
+        Ok(())
+    }
+}
+
+fn update_items(
+    mut events: EventReader<AssetEvent<ItemDefinitions>>,
+    handle: Res<DataHandle<ItemDefinitions>>,
+    definitions: Res<Assets<ItemDefinitions>>,
+    mut items: ResMut<Items>,
+) {
+    // The handle changes when another level is loaded.
+    let reloaded = events
+        .read()
+        .any(|event| event.is_loaded_with_dependencies(&handle.0) || event.is_modified(&handle.0));
+    if !reloaded && !handle.is_changed() {
+        return;
+    }
+    if let Some(definitions) = definitions.get(&handle.0) {
+        items.0 = definitions.clone();
+    }
+}
+
+/// The inventory as it was the last time it changed.
+#[derive(Resource, Default)]
+struct AnnouncedInventory(Inventory);
+
+/// Whatever the run starts out with, from a save or from scratch, isn't a change.
+fn reset_announced(inventory: Res<Inventory>, mut announced: ResMut<AnnouncedInventory>) {
+    announced.0 = inventory.clone();
+}
+
+/// Compare the inventory with how it was the last time it changed, so every change is
+/// announced no matter where it came from.
+fn announce_changes(
+    mut commands: Commands,
+    inventory: Res<Inventory>,
+    mut previous: ResMut<AnnouncedInventory>,
+) {
+    if !inventory.is_changed() {
+        return;
+    }
+    let previous = &mut previous.0;
+    let kinds = previous.stacks.iter().chain(&inventory.stacks);
+    let mut announced = HashSet::new();
+    for stack in kinds {
+        let (before, after) = (previous.count(&stack.item), inventory.count(&stack.item));
+        if before != after && announced.insert(stack.item.clone()) {
+            commands.trigger(InventoryChanged {
+                item: stack.item.clone(),
+                previous: before,
+                count: after,
+            });
+        }
+    }
+    *previous = inventory.clone();
+}
+
+#[derive(Component)]
+struct InventoryStrip;
+
+fn spawn_inventory_strip(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Px(20.0),
+                width: Percent(100.0),
+                justify_content: JustifyContent::Center,
+                column_gap: Px(10.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert((Name::new("Inventory"), StateScoped(Screen::Playing)))
+        .insert(InventoryStrip);
+}
+
+fn update_inventory_strip(
+    mut commands: Commands,
+    inventory: Res<Inventory>,
+    items: Res<Items>,
+    strip: Query<(Entity, Ref<InventoryStrip>)>,
+) {
+    for (strip, marker) in &strip {
+        if !inventory.is_changed() && !items.is_changed() && !marker.is_added() {
+            continue;
+        }
+        let mut stacks: Vec<_> = inventory.stacks().iter().collect();
+        stacks.sort_by_key(|stack| !items.is_key_item(&stack.item));
+
+        commands
+            .entity(strip)
+            .despawn_descendants()
+            .with_children(|strip| {
+                for stack in stacks {
+                    let item = items.get(&stack.item);
+                    let name = item.map_or(stack.item.as_str(), |item| item.name.as_str());
+                    strip
+                        .spawn(NodeBundle {
+                            style: Style {
+                                min_width: Px(48.0),
+                                height: Px(48.0),
+                                padding: UiRect::all(Px(4.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: BackgroundColor(ui_palette::NODE_BACKGROUND),
+                            ..default()
+                        })
+                        .insert(Name::new(format!("Item {name}")))
+                        .with_children(|slot| {
+                            match item.and_then(|item| item.icon_handle.clone()) {
+                                Some(icon) => {
+                                    slot.spawn(ImageBundle {
+                                        style: Style {
+                                            width: Px(40.0),
+                                            height: Px(40.0),
+                                            ..default()
+                                        },
+                                        image: UiImage::new(icon),
+                                        ..default()
+                                    });
+                                }
+                                None => {
+                                    slot.spawn(item_text(name.into()));
+                                }
+                            }
+                            if stack.count > 1 {
+                                slot.spawn(item_text(stack.count.to_string()))
+                                    .insert(Style {
+                                        position_type: PositionType::Absolute,
+                                        right: Px(2.0),
+                                        bottom: Px(0.0),
+                                        ..default()
+                                    });
+                            }
+                        });
+                }
+            });
+    }
+}
+
+fn item_text(text: String) -> TextBundle {
+    TextBundle::from_section(
+        text,
+        TextStyle {
+            font_size: 18.0,
+            color: ui_palette::LABEL_TEXT,
+            ..default()
+        },
+    )
+}
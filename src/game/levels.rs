@@ -0,0 +1,187 @@
+//! The islands that can be played are listed in `assets/data/levels.ron`. Everything else about
+//! an island lives in data files named after it, e.g. `data/island.cycles.ron` for the level
+//! `island`, and is loaded again whenever another level becomes the [`CurrentLevel`].
+
+use bevy::{asset::LoadContext, prelude::*};
+use serde::Deserialize;
+
+use super::{
+    cycles::CycleDefinitions,
+    data::{init_data_file, unique_ids, DataAsset, DataError, DataHandle},
+    inventory::ItemDefinitions,
+    progression::ProgressionDefinition,
+    records::Records,
+    save::{read_slot, ActiveSlot},
+    spawn::{interactable::InteractableDefinitions, overlay::LevelOverlay},
+};
+
+/// The level that is played when there is no save to continue.
+pub const FIRST_LEVEL: &str = "island";
+
+pub(super) fn plugin(app: &mut App) {
+    init_data_file::<LevelDefinitions>(app);
+    app.init_resource::<Levels>();
+    app.init_resource::<CurrentLevel>();
+    app.add_systems(
+        PreUpdate,
+        (
+            update_levels,
+            load_level_data.run_if(resource_changed::<CurrentLevel>),
+        )
+            .chain(),
+    );
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug, Clone, Default)]
+pub struct LevelDefinitions {
+    /// Each level is unlocked by finishing the one before it.
+    pub levels: Vec<LevelDefinition>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LevelDefinition {
+    /// Unique name that remembers the level in saves, and the prefix of its data files.
+    pub id: String,
+    /// Name shown to the player.
+    pub name: String,
+    /// Path of the glTF file with the terrain, relative to the assets folder.
+    pub terrain: String,
+    pub player_spawn: PlayerSpawn,
+    pub comet: Placement,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct PlayerSpawn {
+    pub translation: [f32; 3],
+    /// Direction the player looks at, in degrees around the vertical axis.
+    pub yaw: f32,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct Placement {
+    pub translation: [f32; 3],
+    /// Euler angles in degrees, applied in Y, X, Z order.
+    #[serde(default)]
+    pub rotation: [f32; 3],
+    #[serde(default = "one")]
+    pub scale: f32,
+}
+
+fn one() -> f32 {
+    1.0
+}
+
+impl Placement {
+    pub fn transform(&self) -> Transform {
+        let [y, x, z] = self.rotation.map(f32::to_radians);
+        Transform {
+            translation: Vec3::from_array(self.translation),
+            rotation: Quat::from_euler(EulerRot::YXZ, y, x, z),
+            scale: Vec3::splat(self.scale),
+        }
+    }
+}
+
+/// The level definitions in use.
+#[derive(Resource, Default, Debug)]
+pub struct Levels(pub LevelDefinitions);
+
+impl Levels {
+    pub fn get(&self, id: &str) -> Option<&LevelDefinition> {
+        self.0.levels.iter().find(|level| level.id == id)
+    }
+
+    /// Whether the level can be started, given the finished runs.
+    pub fn is_unlocked(&self, id: &str, records: &Records) -> bool {
+        match self.0.levels.iter().position(|level| level.id == id) {
+            Some(0) => true,
+            Some(index) => records.has_finished(&self.0.levels[index - 1].id),
+            None => false,
+        }
+    }
+
+    pub fn name<'a>(&'a self, id: &'a str) -> &'a str {
+        self.get(id).map_or(id, |level| level.name.as_str())
+    }
+}
+
+/// Id of the level that is played.
+#[derive(Resource, Debug, Clone, PartialEq, Eq)]
+pub struct CurrentLevel(pub String);
+
+impl FromWorld for CurrentLevel {
+    /// Continue with the level of the active slot's run, if there is one.
+    fn from_world(world: &mut World) -> Self {
+        let slot = world.get_resource_or_insert_with(ActiveSlot::default).0;
+        Self(read_slot(slot).map_or_else(|| FIRST_LEVEL.into(), |save| save.level))
+    }
+}
+
+impl CurrentLevel {
+    /// Path of one of the level's data files, e.g. `data/island.cycles.ron` for `"cycles.ron"`.
+    pub fn data_file(&self, extension: &str) -> String {
+        format!("data/{}.{extension}", self.0)
+    }
+}
+
+impl DataAsset for LevelDefinitions {
+    const EXTENSION: &'static str = "levels.ron";
+
+    /// The levels are listed in one file for all of them.
+    fn path(_level: &CurrentLevel) -> String {
+        "data/levels.ron".into()
+    }
+
+    fn validate(&mut self, _load_context: &mut LoadContext<'_>) -> Result<(), DataError> {
+        if self.levels.is_empty() {
+            return Err(DataError::Invalid(
+                "there has to be at least one level".into(),
+            ));
+        }
+        unique_ids("level", self.levels.iter().map(|level| level.id.as_str()))?;
+        Ok(())
+    }
+}
+
+fn update_levels(
+    mut events: EventReader<AssetEvent<LevelDefinitions>>,
+    handle: Res<DataHandle<LevelDefinitions>>,
+    definitions: Res<Assets<LevelDefinitions>>,
+    mut levels: ResMut<Levels>,
+    mut current_level: ResMut<CurrentLevel>,
+) {
+    for event in events.read() {
+        if !event.is_loaded_with_dependencies(&handle.0) && !event.is_modified(&handle.0) {
+            continue;
+        }
+        let Some(definitions) = definitions.get(&handle.0) else {
+            continue;
+        };
+        levels.0 = definitions.clone();
+        // A save may refer to a level that has been removed since.
+        if levels.get(&current_level.0).is_none() {
+            warn!("There is no level called \"{}\"", current_level.0);
+            current_level.0 = levels.0.levels[0].id.clone();
+        }
+    }
+}
+
+/// Replace the data files of the previous level with the current level's.
+fn load_level_data(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    current_level: Res<CurrentLevel>,
+) {
+    let (asset_server, level) = (&asset_server, &current_level);
+    commands.insert_resource(DataHandle::<CycleDefinitions>::load(asset_server, level));
+    commands.insert_resource(DataHandle::<InteractableDefinitions>::load(
+        asset_server,
+        level,
+    ));
+    commands.insert_resource(DataHandle::<ProgressionDefinition>::load(
+        asset_server,
+        level,
+    ));
+    commands.insert_resource(DataHandle::<ItemDefinitions>::load(asset_server, level));
+    commands.insert_resource(DataHandle::<LevelOverlay>::load(asset_server, level));
+}
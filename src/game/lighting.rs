@@ -0,0 +1,116 @@
+//! Sun, moon, sky and ambient light follow the time of day of the [`WorldClock`].
+//!
+//! The sun and moon orbit together, the moon trailing the sun by an angle that grows by one
+//! phase with every cycle. Which part of the moon is lit follows from where the sun is, so the
+//! sky shader and the moonlight agree on the phase without tracking it separately.
+
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+
+use crate::{screen::Screen, AppSet};
+
+use super::{
+    assets::update_asset,
+    clock::WorldClock,
+    logic::CurrentCycle,
+    spawn::level::{Moon, SkyMaterial, Sun, SunPivot},
+};
+
+/// Phases the moon goes through, one per cycle.
+const MOON_PHASES: usize = 8;
+/// Illuminance of the full moon. Far brighter than the real one, so nights stay playable.
+const MOONLIGHT: f32 = 400.0;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (rotate_sun, light_sun, light_moon, update_sky)
+            .in_set(AppSet::Update)
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+/// How bright the sun shines at the given time of day, from 0 at night to 1 at noon.
+pub fn daylight(progress: f32) -> f32 {
+    (progress * 2.0 * PI).sin().clamp(0.0, 1.0)
+}
+
+/// Rotation of the [`SunPivot`] at the given time of day. The sun is on its local X axis.
+pub fn sky_rotation(progress: f32) -> Quat {
+    Quat::from_euler(EulerRot::YXZ, 0.0, -PI / 4.0, 2.0 * PI * progress)
+}
+
+/// How far the moon has gone through its phases after the given number of cycles, from 0 at
+/// new moon over 0.5 at full moon back to 1. The first cycle starts at full moon.
+pub fn moon_phase(cycles_elapsed: usize) -> f32 {
+    ((cycles_elapsed + MOON_PHASES / 2) % MOON_PHASES) as f32 / MOON_PHASES as f32
+}
+
+/// Direction of the moon relative to the [`SunPivot`].
+fn moon_direction(phase: f32) -> Vec3 {
+    Quat::from_rotation_z(TAU * phase) * Vec3::X
+}
+
+fn rotate_sun(clock: Res<WorldClock>, mut pivots: Query<&mut Transform, With<SunPivot>>) {
+    for mut pivot in &mut pivots {
+        pivot.rotation = sky_rotation(clock.progress);
+    }
+}
+
+fn light_sun(
+    clock: Res<WorldClock>,
+    mut suns: Query<&mut DirectionalLight, With<Sun>>,
+    mut environments: Query<&mut EnvironmentMapLight, With<Camera>>,
+) {
+    let daylight = daylight(clock.progress);
+    for mut sun in &mut suns {
+        sun.illuminance = light_consts::lux::AMBIENT_DAYLIGHT * daylight;
+    }
+    for mut environment in &mut environments {
+        environment.intensity =
+            light_consts::lux::FULL_MOON_NIGHT.lerp(light_consts::lux::DARK_OVERCAST_DAY, daylight);
+    }
+}
+
+fn light_moon(
+    clock: Res<WorldClock>,
+    current_cycle: Res<CurrentCycle>,
+    mut moons: Query<(&mut Transform, &mut DirectionalLight), With<Moon>>,
+) {
+    let phase = moon_phase(current_cycle.1);
+    let direction = moon_direction(phase);
+    let lit_fraction = (1.0 - (TAU * phase).cos()) / 2.0;
+    // Fade out as the moon sets, rather than shining through the ground.
+    let elevation = (sky_rotation(clock.progress) * direction).y;
+    let illuminance = MOONLIGHT * lit_fraction * (elevation * 4.0).clamp(0.0, 1.0);
+    for (mut transform, mut light) in &mut moons {
+        *transform = Transform::default().looking_to(-direction, Vec3::Z);
+        light.illuminance = illuminance;
+    }
+}
+
+fn update_sky(
+    clock: Res<WorldClock>,
+    current_cycle: Res<CurrentCycle>,
+    sky_materials: Query<&Handle<SkyMaterial>>,
+    mut materials: ResMut<Assets<SkyMaterial>>,
+) {
+    let daylight = daylight(clock.progress);
+    let rotation = sky_rotation(clock.progress);
+    let moon = rotation * moon_direction(moon_phase(current_cycle.1));
+    let rotation = Mat4::from_quat(rotation);
+    let moon = moon.extend(0.0);
+    for material in &sky_materials {
+        update_asset(
+            &mut materials,
+            material,
+            |sky| sky.time.x != daylight || sky.rotation != rotation || sky.moon != moon,
+            |sky| {
+                sky.time.x = daylight;
+                sky.rotation = rotation;
+                sky.moon = moon;
+            },
+        );
+    }
+}
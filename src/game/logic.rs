@@ -1,10 +1,11 @@
-use std::{f32::consts::PI, time::Duration};
+use std::time::Duration;
 
-use bevy::{animation::RepeatAnimation, prelude::*};
+use bevy::{animation::RepeatAnimation, ecs::system::SystemParam, prelude::*, utils::HashSet};
 use bevy_rapier3d::{
     plugin::RapierContext,
     prelude::{ColliderDisabled, CollisionGroups, Group, QueryFilter, RigidBodyDisabled},
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{
     game::audio::sfx::GroundMaterial,
@@ -16,31 +17,34 @@ use super::{
     animation::Animations,
     assets::SfxKey,
     audio::{sfx::PlaySfx, soundtrack::PlaySoundtrack},
+    clock::{ClockSource, WorldClock},
+    cycles::Cycles,
+    input::PlayerInput,
+    inventory::Inventory,
+    levels::{CurrentLevel, Levels},
     movement::MovementController,
+    progression::{DisabledByProgress, Progress},
+    save::SaveGame,
     spawn::{
-        level::{SkyMaterial, Sun, SunPivot, Terrain},
+        cycle_scene::SpawnCycleScene,
+        interactable::Requirement,
+        level::Terrain,
         player::{Player, PlayerCamera},
     },
+    speedrun::{Milestone, MilestoneReached},
 };
 
 pub(super) fn plugin(app: &mut App) {
-    app.insert_resource(CurrentCycle(Cycle::One, 0))
-        .insert_resource(DayProgress(0.0))
+    app.insert_resource(CurrentCycle(Cycle::default(), 0))
         .insert_resource(CurrentHighlighted(None))
         .insert_resource(BoatPosition::default())
-        .insert_resource(Inventory::default());
-    /*  .insert_resource(Inventory {
-    hourglass: true,
-    sapling: false,
-    monument_finished: true,
-    });*/
+        .init_resource::<ConsumedInteractables>();
     app.observe(on_cycle_changed);
     app.observe(cast_ground_ray);
     app.register_type::<Interactable>();
     app.add_systems(
         Update,
         (
-            animate_sun,
             //   animate_water,
             tick_animation_timers,
         )
@@ -49,7 +53,10 @@ pub(super) fn plugin(app: &mut App) {
     );
     app.add_systems(
         Update,
-        (respawn, handle_interaction)
+        (
+            respawn.run_if(not(resource_exists::<FreeFlight>)),
+            handle_interaction,
+        )
             .in_set(AppSet::Update)
             .run_if(in_state(PlayState::InGame)),
     );
@@ -63,27 +70,15 @@ pub(super) fn plugin(app: &mut App) {
         )
             .run_if(in_state(PlayState::InGame)),
     );
-    app.add_systems(OnEnter(PlayState::InMenu), animate_sun);
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Component)]
-pub enum Cycle {
-    One,
-    Two,
-    Three,
-    Final,
-}
+/// Id of one of the [`Cycles`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Component, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Cycle(pub String);
 
-impl Cycle {
-    pub fn next(self) -> Self {
-        match self {
-            Cycle::One => Cycle::Two,
-            Cycle::Two => Cycle::Three,
-            Cycle::Three => Cycle::One,
-            Cycle::Final => Cycle::Final,
-        }
-    }
-}
+/// In-game years that pass with every cycle.
+pub const YEARS_PER_CYCLE: usize = 12000;
 
 #[derive(Resource)]
 pub struct CurrentCycle(pub Cycle, pub usize);
@@ -94,8 +89,10 @@ pub struct CycleChanged(pub Cycle);
 #[derive(Event)]
 pub struct Footstep;
 
-#[derive(Resource)]
-pub struct DayProgress(f32);
+/// While this resource exists, the player is moved around freely and isn't respawned when below
+/// the island, e.g. while the level editor is open.
+#[derive(Resource, Debug)]
+pub struct FreeFlight;
 
 #[derive(Resource)]
 pub struct CurrentHighlighted(pub Option<Entity>);
@@ -104,14 +101,17 @@ pub struct CurrentHighlighted(pub Option<Entity>);
 pub struct Interactable {
     pub highlight_mesh: Option<Entity>,
     pub text: String,
+    /// Conditions that all have to be met before the interactable can be used.
+    #[reflect(ignore)]
+    pub requirements: Vec<Requirement>,
+    /// Shown instead of the prompt while the requirements are not met.
+    pub locked_text: String,
 }
 
+/// Ids of interactables that have been used up during this run and should not
+/// reappear when the level is spawned again.
 #[derive(Resource, Default)]
-pub struct Inventory {
-    hourglass: bool,
-    sapling: bool,
-    monument_finished: bool,
-}
+pub struct ConsumedInteractables(pub HashSet<String>);
 
 #[derive(Component)]
 pub struct AnimationTimer(Timer);
@@ -124,8 +124,53 @@ impl Interactable {
         Self {
             highlight_mesh: None,
             text,
+            requirements: vec![],
+            locked_text: String::new(),
         }
     }
+
+    pub fn with_requirements(
+        mut self,
+        requirements: Vec<Requirement>,
+        locked_text: String,
+    ) -> Self {
+        self.requirements = requirements;
+        self.locked_text = locked_text;
+        self
+    }
+}
+
+/// The state of the run that interaction requirements are checked against.
+#[derive(SystemParam)]
+pub struct RequirementCheck<'w> {
+    inventory: Res<'w, Inventory>,
+    progress: Res<'w, Progress>,
+    current_cycle: Res<'w, CurrentCycle>,
+    clock: Res<'w, WorldClock>,
+}
+
+impl RequirementCheck<'_> {
+    pub fn is_met(&self, requirement: &Requirement) -> bool {
+        match requirement {
+            Requirement::HasItem(item) => self.inventory.has(item),
+            Requirement::Flag(flag) => self.progress.has_flag(flag),
+            Requirement::Cycle(cycle) => self.current_cycle.0 .0 == *cycle,
+            Requirement::TimeOfDay { from, to } => {
+                let time = self.clock.progress;
+                match from <= to {
+                    true => (*from..=*to).contains(&time),
+                    // The window wraps around midnight.
+                    false => time >= *from || time <= *to,
+                }
+            }
+        }
+    }
+
+    pub fn are_met(&self, requirements: &[Requirement]) -> bool {
+        requirements
+            .iter()
+            .all(|requirement| self.is_met(requirement))
+    }
 }
 
 #[derive(Component)]
@@ -144,91 +189,39 @@ pub struct BoatPosition {
 fn on_cycle_changed(
     trigger: Trigger<CycleChanged>,
     mut current_cycle: ResMut<CurrentCycle>,
-    mut colliders: Query<(&mut Transform, &Cycle), Without<AnimationPlayer>>,
     mut prompt: Query<&mut Text, With<PromptText>>,
     mut commands: Commands,
     mut boat: Query<&mut Transform, With<AnimationPlayer>>,
     mut boat_position: ResMut<BoatPosition>,
+    cycles: Res<Cycles>,
 ) {
-    if current_cycle.0 == Cycle::Final {
+    if cycles.is_terminal(&current_cycle.0) {
         return;
     }
 
-    if trigger.event().0 == Cycle::Final {
+    let soundtrack = cycles.soundtrack(&trigger.event().0);
+    if cycles.soundtrack(&current_cycle.0) != soundtrack {
         commands.trigger(PlaySoundtrack::Disable);
+        if let Some(soundtrack) = soundtrack {
+            commands.trigger(PlaySoundtrack::Handle(soundtrack));
+        }
     }
 
-    current_cycle.0 = trigger.event().0;
+    current_cycle.0 = trigger.event().0.clone();
     prompt.single_mut().sections[0].value = "".into();
 
-    for (mut transform, cycle) in colliders.iter_mut() {
-        if *cycle != current_cycle.0 {
-            let depth_modifier = match *cycle {
-                Cycle::One => 1.0,
-                Cycle::Two => 2.0,
-                Cycle::Three => 3.0,
-                Cycle::Final => 4.0,
-            };
-            transform.translation.y = -100.0 * depth_modifier;
-        } else {
-            transform.translation.y = 0.0;
-        }
-    }
+    commands.trigger(SpawnCycleScene(current_cycle.0.clone()));
 
     current_cycle.1 += 1;
     commands.trigger(PlaySfx::Key(SfxKey::CycleChange));
 
-    *boat.single_mut() = boat_position.initial_transform;
-    boat_position.docked_at_island = false;
-}
-
-fn animate_sun(
-    mut pivot: Query<&mut Transform, With<SunPivot>>,
-    mut sun: Query<&mut DirectionalLight, With<Sun>>,
-    mut day_progress: ResMut<DayProgress>,
-    mut environment: Query<&mut EnvironmentMapLight, With<Camera>>,
-    sky_materials: Query<&Handle<SkyMaterial>>,
-    mut mats: ResMut<Assets<SkyMaterial>>,
-    current_cycle: Res<CurrentCycle>,
-    time: Res<Time>,
-    input: Res<ButtonInput<KeyCode>>,
-    inventory: Res<Inventory>,
-    mut commands: Commands,
-    boat_position: Res<BoatPosition>,
-) {
-    // TODO: Cleaner solution for pausing time
-    if boat_position.currently_rowing {
-        return;
-    }
-
-    let time_modifier = match inventory.hourglass && input.pressed(KeyCode::KeyQ) {
-        false => 1.0,
-        true => 30.0,
-    };
-    day_progress.0 += time.delta_seconds() * time_modifier / 60.0;
-    if day_progress.0 >= 1.0 {
-        day_progress.0 -= 1.0;
-        let next_cycle = if inventory.monument_finished {
-            Cycle::Final
-        } else {
-            current_cycle.0.next()
-        };
-        commands.trigger(CycleChanged(next_cycle));
+    // The boat may be part of the scene that is being replaced.
+    if let Ok(mut boat) = boat.get_single_mut() {
+        *boat = boat_position.initial_transform;
     }
+    boat_position.docked_at_island = false;
 
-    let mut pivot = pivot.single_mut();
-    let angle = 2.0 * PI * day_progress.0;
-    pivot.rotation = Quat::from_euler(EulerRot::YXZ, 0.0, -PI / 4.0, angle);
-    let brightness_factor = (day_progress.0 * 2.0 * PI).sin().clamp(0.0, 1.0);
-    sun.single_mut().illuminance = light_consts::lux::AMBIENT_DAYLIGHT * brightness_factor;
-    environment.single_mut().intensity = light_consts::lux::FULL_MOON_NIGHT
-        .lerp(light_consts::lux::DARK_OVERCAST_DAY, brightness_factor);
-
-    for material in sky_materials.iter() {
-        if let Some(sky) = mats.get_mut(material) {
-            sky.time.x = brightness_factor;
-        }
-    }
+    commands.trigger(SaveGame);
 }
 
 /*
@@ -291,7 +284,14 @@ fn disable_intersecting_colliders(
 // TODO: Custom Schedule instead of local timer?
 fn reenable_colliders(
     mut commands: Commands,
-    disabled_colliders: Query<Entity, (With<ColliderDisabled>, Without<Interactable>)>,
+    disabled_colliders: Query<
+        Entity,
+        (
+            With<ColliderDisabled>,
+            Without<Interactable>,
+            Without<DisabledByProgress>,
+        ),
+    >,
     mut timer: Local<f32>,
     time: Res<Time>,
 ) {
@@ -331,13 +331,17 @@ fn check_for_interactables(
 }
 
 fn handle_interaction(
-    input: Res<ButtonInput<KeyCode>>,
+    input: Res<PlayerInput>,
     current_highlighted: Res<CurrentHighlighted>,
-    interactables: Query<Entity, With<Interactable>>,
+    interactables: Query<(Entity, &Interactable)>,
+    requirements: RequirementCheck,
     mut commands: Commands,
 ) {
-    for object in interactables.iter() {
-        if current_highlighted.0 == Some(object) && input.just_pressed(KeyCode::KeyE) {
+    for (object, interactable) in interactables.iter() {
+        if current_highlighted.0 == Some(object)
+            && input.interact
+            && requirements.are_met(&interactable.requirements)
+        {
             commands.trigger_targets(Interacted, object);
         }
     }
@@ -347,7 +351,9 @@ fn update_highlight_mesh(
     highlighted: Res<CurrentHighlighted>,
     interactables: Query<(Entity, &Interactable)>,
     mut prompt: Query<&mut Text, With<PromptText>>,
+    requirements: RequirementCheck,
     mut commands: Commands,
+    mut shown_prompt: Local<String>,
 ) {
     let mut something_highlighted = false;
     let mut text = prompt.single_mut();
@@ -355,96 +361,32 @@ fn update_highlight_mesh(
         if let Some(highlight_mesh) = interactable.highlight_mesh {
             if highlighted.0 == Some(entity) {
                 commands.entity(highlight_mesh).insert(Visibility::Visible);
-                text.sections[0].value = interactable.text.clone();
+                *shown_prompt = match requirements.are_met(&interactable.requirements) {
+                    true => interactable.text.clone(),
+                    false => interactable.locked_text.clone(),
+                };
+                text.sections[0].value.clone_from(&shown_prompt);
                 something_highlighted = true;
             } else {
                 commands.entity(highlight_mesh).insert(Visibility::Hidden);
             }
         }
     }
-    if !something_highlighted && text.sections[0].value.starts_with("E: ") {
+    // Leave prompts alone that were set by something else in the meantime.
+    if !something_highlighted && text.sections[0].value == *shown_prompt {
         text.sections[0].value = "".into();
     }
 }
 
 // Content specific logic
 
-pub fn on_hourglass_taken(
-    trigger: Trigger<Interacted>,
-    mut inventory: ResMut<Inventory>,
-    mut prompt: Query<&mut Text, With<PromptText>>,
-    mut commands: Commands,
-) {
-    inventory.hourglass = true;
-    prompt.single_mut().sections[0].value = "Hold Q: Fast-forward time".into();
-    commands.trigger(PlaySfx::Key(SfxKey::PickupHourglass));
-    commands.entity(trigger.entity()).despawn();
-}
-
-pub fn on_sapling_taken(
-    trigger: Trigger<Interacted>,
-    mut inventory: ResMut<Inventory>,
-    q_sapling: Query<(Entity, &Name)>,
-    mut commands: Commands,
-) {
-    inventory.sapling = true;
-    commands.trigger(PlaySfx::Key(SfxKey::Harvest));
-    for (entity, name) in q_sapling.iter() {
-        if name.as_str().contains("Sapling") || name.as_str().contains("TreeLower") {
-            commands.entity(entity).despawn_recursive();
-        }
-    }
-    commands.entity(trigger.entity()).insert(ColliderDisabled);
-}
-
-pub fn on_sapling_planted(
-    trigger: Trigger<Interacted>,
-    mut inventory: ResMut<Inventory>,
-    q_sapling: Query<(Entity, &Name)>,
-    mut commands: Commands,
-) {
-    inventory.sapling = false;
-    commands.trigger(PlaySfx::Key(SfxKey::Harvest));
-    for (entity, name) in q_sapling.iter() {
-        if name.as_str().contains("FinalSap") {
-            commands.entity(entity).insert(Visibility::Visible);
-        }
-        if name.as_str().contains("TreeUpper") {
-            commands
-                .entity(entity)
-                .insert(Visibility::Visible)
-                .remove::<ColliderDisabled>();
-        }
-    }
-    commands.entity(trigger.entity()).insert(ColliderDisabled);
-}
-
-pub fn on_monument_finished(
-    trigger: Trigger<Interacted>,
-    q_sapling: Query<(Entity, &Name)>,
-    mut inventory: ResMut<Inventory>,
-    mut commands: Commands,
-) {
-    inventory.monument_finished = true;
-    println!("Monument finished");
-    commands.trigger(PlaySfx::Key(SfxKey::Chisel));
-    for (entity, name) in q_sapling.iter() {
-        if name.as_str().contains("finished_monument") {
-            commands.entity(entity).insert(Visibility::Visible);
-        }
-        if name.as_str().contains("Stone") {
-            commands.entity(entity).insert(Visibility::Hidden);
-        }
-    }
-    commands.entity(trigger.entity()).insert(ColliderDisabled);
-}
-
 pub fn on_game_finished(
     _trigger: Trigger<Interacted>,
     mut commands: Commands,
     mut next_screen: ResMut<NextState<Screen>>,
 ) {
     commands.trigger(PlaySfx::Key(SfxKey::CycleChange));
+    commands.trigger(MilestoneReached(Milestone::GameFinished));
     next_screen.set(Screen::Credits);
 }
 
@@ -456,6 +398,7 @@ pub fn on_boat_used(
     mut boat_root: Query<(Entity, &mut AnimationPlayer)>,
     mut player: Query<(Entity, &mut Transform, &mut MovementController), With<Player>>,
     mut boat_position: ResMut<BoatPosition>,
+    mut clock: ResMut<WorldClock>,
 ) {
     prompt.single_mut().sections[0].value = "".into();
     commands.trigger(PlaySfx::Key(SfxKey::Row));
@@ -492,6 +435,8 @@ pub fn on_boat_used(
     transform.translation = Vec3::new(0.0, 1.0, 0.0);
     boat_position.docked_at_island = !boat_position.docked_at_island;
     boat_position.currently_rowing = true;
+    // Time stands still while rowing.
+    clock.pause(ClockSource::Rowing);
 }
 
 fn tick_animation_timers(
@@ -519,6 +464,7 @@ fn on_boat_ride_finished(
         With<Player>,
     >,
     mut boat_position: ResMut<BoatPosition>,
+    mut clock: ResMut<WorldClock>,
 ) {
     let (player, mut transform, global_transform, mut controller) = player.single_mut();
     controller.disabled = false;
@@ -533,16 +479,22 @@ fn on_boat_ride_finished(
         .remove::<ColliderDisabled>();
     transform.translation = global_transform.translation() + Vec3::new(0.5, 1.0, 0.5);
     boat_position.currently_rowing = false;
+    clock.resume(ClockSource::Rowing);
 }
 
 fn respawn(
     mut transform: Query<&mut Transform, With<Player>>,
     mut commands: Commands,
     current_cycle: Res<CurrentCycle>,
+    cycles: Res<Cycles>,
+    levels: Res<Levels>,
+    current_level: Res<CurrentLevel>,
 ) {
     let mut transform = transform.single_mut();
     if transform.translation.y <= 0.0 {
-        transform.translation = Vec3::new(5.52, 4.4, -33.66);
-        commands.trigger(CycleChanged(current_cycle.0.next()));
+        if let Some(level) = levels.get(&current_level.0) {
+            transform.translation = Vec3::from_array(level.player_spawn.translation);
+        }
+        commands.trigger(CycleChanged(cycles.next(&current_cycle.0)));
     }
 }
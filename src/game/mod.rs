@@ -2,20 +2,54 @@
 
 use bevy::prelude::*;
 
+pub mod achievements;
 pub mod animation;
 pub mod assets;
 pub mod audio;
+pub mod calendar;
+pub mod clock;
+pub mod comet;
+pub mod cycles;
+pub mod data;
+pub mod ghost;
+pub mod hourglass;
+pub mod input;
+pub mod inventory;
+pub mod levels;
+pub mod lighting;
 pub mod logic;
-mod movement;
+pub mod movement;
+pub mod progression;
+pub mod records;
+pub mod save;
 pub mod spawn;
+pub mod speedrun;
 
 pub(super) fn plugin(app: &mut App) {
+    // The current level decides which data files the other plugins load.
+    app.add_plugins(levels::plugin);
     app.add_plugins((
         animation::plugin,
         audio::plugin,
+        input::plugin,
+        inventory::plugin,
         assets::plugin,
+        cycles::plugin,
         movement::plugin,
         spawn::plugin,
         logic::plugin,
+        progression::plugin,
+        save::plugin,
+        records::plugin,
+        speedrun::plugin,
+        ghost::plugin,
+        achievements::plugin,
+    ));
+    app.add_plugins((
+        clock::plugin,
+        hourglass::plugin,
+        lighting::plugin,
+        calendar::plugin,
+        comet::plugin,
     ));
 }
@@ -3,17 +3,14 @@
 //! If you want to move the player in a smoother way,
 //! consider using a [fixed timestep](https://github.com/bevyengine/bevy/blob/latest/examples/movement/physics_in_fixed_timestep.rs).
 
-use bevy::{
-    input::{mouse::MouseMotion, InputSystem},
-    prelude::*,
-};
+use bevy::prelude::*;
 use bevy_rapier3d::{
     control::KinematicCharacterController, prelude::KinematicCharacterControllerOutput,
 };
 
 use crate::{screen::PlayState, AppSet};
 
-use super::{logic::Footstep, spawn::player::CameraPivot};
+use super::{input::PlayerInput, logic::Footstep, spawn::player::CameraPivot};
 
 pub(super) fn plugin(app: &mut App) {
     // Record directional input as movement controls.
@@ -29,7 +26,6 @@ pub(super) fn plugin(app: &mut App) {
         PreUpdate,
         record_movement_controller
             .in_set(AppSet::RecordInput)
-            .after(InputSystem)
             .run_if(in_state(PlayState::InGame)),
     );
 
@@ -55,28 +51,13 @@ pub struct MovementController {
 pub struct FootstepTimer(pub f32);
 
 fn record_movement_controller(
-    input: Res<ButtonInput<KeyCode>>,
+    input: Res<PlayerInput>,
     mut controller_query: Query<(&mut MovementController, &KinematicCharacterControllerOutput)>,
     mut footstep_timer: ResMut<FootstepTimer>,
 ) {
-    // Collect directional input.
-    let mut intent = Vec2::ZERO;
-    if input.pressed(KeyCode::KeyW) {
-        intent.y += 1.0;
-    }
-    if input.pressed(KeyCode::KeyS) {
-        intent.y -= 1.0;
-    }
-    if input.pressed(KeyCode::KeyA) {
-        intent.x -= 1.0;
-    }
-    if input.pressed(KeyCode::KeyD) {
-        intent.x += 1.0;
-    }
-
     // Normalize so that diagonal movement has the same speed as
     // horizontal and vertical movement.
-    let intent = intent.normalize_or_zero();
+    let intent = input.movement.normalize_or_zero();
 
     // Apply movement intent to controllers.
     for (mut controller, kinematic_output) in controller_query.iter_mut() {
@@ -84,7 +65,7 @@ fn record_movement_controller(
             continue;
         }
         controller.direction = intent;
-        controller.jump = input.pressed(KeyCode::Space) && kinematic_output.grounded;
+        controller.jump = input.jump && kinematic_output.grounded;
         if intent.length() < 0.5 || !kinematic_output.grounded {
             footstep_timer.0 = 0.0;
         }
@@ -149,21 +130,23 @@ fn apply_movement(
 
 fn rotate_camera(
     mut pivot: Query<&mut Transform, With<CameraPivot>>,
-    mut mouse_motion: EventReader<MouseMotion>,
+    mut input: ResMut<PlayerInput>,
 ) {
+    let look = std::mem::take(&mut input.look);
+    if look == Vec2::ZERO {
+        return;
+    }
     for mut transform in pivot.iter_mut() {
-        for motion in mouse_motion.read() {
-            let yaw = -motion.delta.x * 0.003;
-            let pitch = -motion.delta.y * 0.002;
+        let yaw = -look.x * 0.003;
+        let pitch = -look.y * 0.002;
 
-            transform.rotate_y(yaw);
+        transform.rotate_y(yaw);
 
-            let current_pitch = transform.rotation.to_euler(EulerRot::YXZ).1;
-            if (current_pitch > -60.0_f32.to_radians() && pitch < 0.0)
-                || (current_pitch < 60.0_f32.to_radians() && pitch > 0.0)
-            {
-                transform.rotate_local_x(pitch);
-            }
+        let current_pitch = transform.rotation.to_euler(EulerRot::YXZ).1;
+        if (current_pitch > -60.0_f32.to_radians() && pitch < 0.0)
+            || (current_pitch < 60.0_f32.to_radians() && pitch > 0.0)
+        {
+            transform.rotate_local_x(pitch);
         }
     }
 }
@@ -0,0 +1,446 @@
+//! The puzzle is described in `assets/data/island.progression.ron` as a list of steps. A step is
+//! completed by using an interactable once its prerequisites are met, which applies the step's
+//! effects. Some effects are consequences that only apply once a later cycle has begun, like a
+//! planted sapling that has grown into a tree.
+//!
+//! Effects on level objects are recorded and applied again whenever a scene with those objects
+//! is spawned, so the [`Progress`] is all that needs to be saved.
+
+use std::collections::BTreeSet;
+
+use bevy::{asset::LoadContext, ecs::system::SystemParam, prelude::*, ui::Val::*};
+use bevy_rapier3d::prelude::ColliderDisabled;
+use serde::{Deserialize, Serialize};
+
+use crate::{screen::Screen, ui::prelude::*};
+
+use super::{
+    assets::SfxKey,
+    audio::sfx::PlaySfx,
+    data::{init_data_file, unique_ids, DataAsset, DataError, DataHandle},
+    inventory::{Inventory, Items},
+    logic::{CurrentCycle, Interacted, PromptText},
+    spawn::{
+        interactable::{ConsumeInteractable, InteractableId},
+        markers::{read_markers, ProgressObject},
+    },
+    speedrun::{Milestone, MilestoneReached},
+};
+
+/// Set once the monument is finished, which leads to the finale at the end of the day.
+pub const MONUMENT_FLAG: &str = "monument_finished";
+
+pub(super) fn plugin(app: &mut App) {
+    init_data_file::<ProgressionDefinition>(app);
+    app.init_resource::<Progress>();
+    app.observe(complete_steps);
+    app.add_systems(OnEnter(Screen::Playing), spawn_objective);
+    app.add_systems(
+        Update,
+        (
+            apply_consequences.before(apply_object_effects),
+            apply_object_effects.after(read_markers),
+            update_objective,
+        )
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct ProgressionDefinition {
+    /// Level objects that are hidden, and can't be collided with, until a step shows them.
+    /// See [`LevelObject`] for how they are referred to.
+    #[serde(default)]
+    pub hidden: Vec<String>,
+    pub steps: Vec<StepDefinition>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct StepDefinition {
+    /// Unique name that remembers the step in saves.
+    pub id: String,
+    /// Shown in the HUD while this is the next step that can be completed.
+    #[serde(default)]
+    pub objective: Option<String>,
+    /// Id of the interactable that completes the step.
+    pub interact: String,
+    /// Steps that have to be completed first.
+    #[serde(default)]
+    pub after: Vec<String>,
+    /// Flags that have to be set.
+    #[serde(default)]
+    pub requires: Vec<String>,
+    /// Ids of items the player has to carry.
+    #[serde(default)]
+    pub items: Vec<String>,
+    pub effects: Vec<Effect>,
+}
+
+/// What happens when a step is completed. See [`LevelObject`] for how level objects are
+/// referred to.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Effect {
+    SetFlag(String),
+    ClearFlag(String),
+    AddItem(String),
+    RemoveItem(String),
+    /// Make the objects visible, and solid again if they started out hidden.
+    Show(String),
+    Hide(String),
+    /// Despawn the objects for the rest of the run.
+    Remove(String),
+    EnableCollider(String),
+    DisableCollider(String),
+    PlaySfx(SfxKey),
+    Prompt(String),
+    Milestone(Milestone),
+    /// Apply the effects once a later cycle has begun.
+    Later {
+        /// How many cycles have to begin first.
+        #[serde(default = "one_cycle")]
+        cycles: usize,
+        /// Wait until this cycle is the current one, after the given number of cycles have begun.
+        #[serde(default)]
+        cycle: Option<String>,
+        effects: Vec<Effect>,
+    },
+}
+
+fn one_cycle() -> usize {
+    1
+}
+
+impl ProgressionDefinition {
+    pub fn get(&self, id: &str) -> Option<&StepDefinition> {
+        self.steps.iter().find(|step| step.id == id)
+    }
+
+    /// Whether all prerequisites of the step are met and it isn't completed yet.
+    pub fn is_available(
+        &self,
+        step: &StepDefinition,
+        progress: &Progress,
+        inventory: &Inventory,
+    ) -> bool {
+        !progress.is_completed(&step.id)
+            && step.after.iter().all(|id| progress.is_completed(id))
+            && step.requires.iter().all(|flag| progress.has_flag(flag))
+            && step.items.iter().all(|item| inventory.has(item))
+    }
+
+    /// The objective of the first step that can be completed next.
+    pub fn objective(&self, progress: &Progress, inventory: &Inventory) -> Option<&str> {
+        self.steps
+            .iter()
+            .filter(|step| self.is_available(step, progress, inventory))
+            .find_map(|step| step.objective.as_deref())
+    }
+
+    /// The state of a level object after everything that happened during the run.
+    pub fn object_state(&self, object: LevelObject, progress: &Progress) -> ObjectState {
+        let mut state = ObjectState::default();
+        let hidden = self.hidden.iter().any(|hidden| object.is(hidden));
+        if hidden {
+            state.visible = Some(false);
+            state.collider = Some(false);
+        }
+        for effect in &progress.applied {
+            match effect {
+                Effect::Show(id) if object.is(id) => {
+                    state.visible = Some(true);
+                    if hidden {
+                        state.collider = Some(true);
+                    }
+                }
+                Effect::Hide(id) if object.is(id) => state.visible = Some(false),
+                Effect::Remove(id) if object.is(id) => state.removed = true,
+                Effect::EnableCollider(id) if object.is(id) => state.collider = Some(true),
+                Effect::DisableCollider(id) if object.is(id) => state.collider = Some(false),
+                _ => {}
+            }
+        }
+        state
+    }
+}
+
+/// How `hidden` and the effects on level objects refer to an object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelObject<'a> {
+    /// Tagged with a `progress_object` id in Blender, which has to match exactly.
+    Tagged(&'a str),
+    /// Has custom properties, but no `progress_object` id, so nothing refers to it.
+    Untagged,
+    /// Has no custom properties, so it is matched if its name contains the reference.
+    Named(&'a str),
+}
+
+impl LevelObject<'_> {
+    fn is(self, reference: &str) -> bool {
+        match self {
+            LevelObject::Tagged(id) => id == reference,
+            LevelObject::Untagged => false,
+            LevelObject::Named(name) => name.contains(reference),
+        }
+    }
+}
+
+/// How the steps changed a level object. `None` leaves it as it is in the scene.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectState {
+    pub visible: Option<bool>,
+    pub collider: Option<bool>,
+    pub removed: bool,
+}
+
+/// How far the puzzle has been solved during this run.
+#[derive(Resource, Default, Clone, Serialize, Deserialize, Debug)]
+pub struct Progress {
+    /// Ids of the completed steps, in the order they were completed.
+    pub completed: Vec<String>,
+    pub flags: BTreeSet<String>,
+    /// Effects on level objects, in the order they were applied.
+    pub applied: Vec<Effect>,
+    /// Consequences waiting for a later cycle.
+    pub pending: Vec<PendingConsequence>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PendingConsequence {
+    /// Number of elapsed cycles from which on the effects apply.
+    pub elapsed: usize,
+    pub cycle: Option<String>,
+    pub effects: Vec<Effect>,
+}
+
+impl PendingConsequence {
+    fn is_due(&self, current_cycle: &CurrentCycle) -> bool {
+        current_cycle.1 >= self.elapsed
+            && self
+                .cycle
+                .as_ref()
+                .map_or(true, |cycle| current_cycle.0 .0 == *cycle)
+    }
+}
+
+impl Progress {
+    pub fn is_completed(&self, step: &str) -> bool {
+        self.completed.iter().any(|id| id == step)
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.contains(flag)
+    }
+}
+
+impl DataAsset for ProgressionDefinition {
+    const EXTENSION: &'static str = "progression.ron";
+
+    fn validate(&mut self, _load_context: &mut LoadContext<'_>) -> Result<(), DataError> {
+        let ids = unique_ids("step", self.steps.iter().map(|step| step.id.as_str()))?;
+        for step in &self.steps {
+            if let Some(id) = step.after.iter().find(|id| !ids.contains(id.as_str())) {
+                return Err(DataError::Invalid(format!(
+                    "step \"{id}\" is required but not defined"
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Everything that effects change.
+#[derive(SystemParam)]
+struct EffectTargets<'w, 's> {
+    commands: Commands<'w, 's>,
+    progress: ResMut<'w, Progress>,
+    inventory: ResMut<'w, Inventory>,
+    items: Res<'w, Items>,
+    current_cycle: Res<'w, CurrentCycle>,
+    prompt: Query<'w, 's, &'static mut Text, With<PromptText>>,
+}
+
+impl EffectTargets<'_, '_> {
+    fn apply(&mut self, effects: &[Effect]) {
+        for effect in effects {
+            match effect {
+                Effect::SetFlag(flag) => {
+                    self.progress.flags.insert(flag.clone());
+                }
+                Effect::ClearFlag(flag) => {
+                    self.progress.flags.remove(flag);
+                }
+                Effect::AddItem(item) => self.inventory.add(item, 1, &self.items),
+                Effect::RemoveItem(item) => {
+                    self.inventory.remove(item, 1);
+                }
+                Effect::PlaySfx(key) => self.commands.trigger(PlaySfx::Key(*key)),
+                Effect::Prompt(text) => {
+                    if let Ok(mut prompt) = self.prompt.get_single_mut() {
+                        prompt.sections[0].value = text.clone();
+                    }
+                }
+                Effect::Milestone(milestone) => self.commands.trigger(MilestoneReached(*milestone)),
+                Effect::Later {
+                    cycles,
+                    cycle,
+                    effects,
+                } => self.progress.pending.push(PendingConsequence {
+                    elapsed: self.current_cycle.1 + (*cycles).max(1),
+                    cycle: cycle.clone(),
+                    effects: effects.clone(),
+                }),
+                // Applied to the level by `apply_object_effects`.
+                Effect::Show(_)
+                | Effect::Hide(_)
+                | Effect::Remove(_)
+                | Effect::EnableCollider(_)
+                | Effect::DisableCollider(_) => self.progress.applied.push(effect.clone()),
+            }
+        }
+    }
+}
+
+/// Complete the steps that the used interactable is waiting for.
+fn complete_steps(
+    trigger: Trigger<Interacted>,
+    interactables: Query<&InteractableId>,
+    mut targets: EffectTargets,
+    definitions: Res<Assets<ProgressionDefinition>>,
+    handle: Res<DataHandle<ProgressionDefinition>>,
+) {
+    let (Ok(interactable), Some(definition)) = (
+        interactables.get(trigger.entity()),
+        definitions.get(&handle.0),
+    ) else {
+        return;
+    };
+    let mut completed_any = false;
+    for step in &definition.steps {
+        if step.interact != interactable.0
+            || !definition.is_available(step, &targets.progress, &targets.inventory)
+        {
+            continue;
+        }
+        targets.progress.completed.push(step.id.clone());
+        completed_any = true;
+        targets.apply(&step.effects);
+    }
+    if completed_any {
+        targets
+            .commands
+            .trigger_targets(ConsumeInteractable, trigger.entity());
+    }
+}
+
+/// Apply the consequences that were waiting for the cycle that just began.
+fn apply_consequences(mut targets: EffectTargets) {
+    if !targets.current_cycle.is_changed() {
+        return;
+    }
+    let pending = std::mem::take(&mut targets.progress.pending);
+    let (due, pending): (Vec<_>, Vec<_>) = pending
+        .into_iter()
+        .partition(|consequence| consequence.is_due(&targets.current_cycle));
+    targets.progress.pending = pending;
+    for consequence in due {
+        targets.apply(&consequence.effects);
+    }
+}
+
+/// Marks colliders that stay disabled until a step enables them again.
+#[derive(Component, Debug)]
+pub struct DisabledByProgress;
+
+pub(super) fn apply_object_effects(
+    mut commands: Commands,
+    objects: Query<(Entity, Ref<Name>, Option<Ref<ProgressObject>>), Without<Node>>,
+    progress: Res<Progress>,
+    definitions: Res<Assets<ProgressionDefinition>>,
+    handle: Res<DataHandle<ProgressionDefinition>>,
+) {
+    let Some(definition) = definitions.get(&handle.0) else {
+        return;
+    };
+    for (entity, name, progress_object) in &objects {
+        let added = name.is_added() || progress_object.as_ref().is_some_and(Ref::is_added);
+        if !progress.is_changed() && !added {
+            continue;
+        }
+        let object = match progress_object.as_deref() {
+            Some(ProgressObject(Some(id))) => LevelObject::Tagged(id),
+            Some(ProgressObject(None)) => LevelObject::Untagged,
+            None => LevelObject::Named(name.as_str()),
+        };
+        let state = definition.object_state(object, &progress);
+        if state.removed {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        let mut entity = commands.entity(entity);
+        if let Some(visible) = state.visible {
+            entity.insert(match visible {
+                true => Visibility::Inherited,
+                false => Visibility::Hidden,
+            });
+        }
+        match state.collider {
+            Some(true) => {
+                entity.remove::<(ColliderDisabled, DisabledByProgress)>();
+            }
+            Some(false) => {
+                entity.insert((ColliderDisabled, DisabledByProgress));
+            }
+            None => {}
+        }
+    }
+}
+
+#[derive(Component)]
+struct ObjectiveText;
+
+fn spawn_objective(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Px(20.0),
+                bottom: Px(20.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert((Name::new("Objective"), StateScoped(Screen::Playing)))
+        .with_children(|overlay| {
+            overlay
+                .spawn(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: ui_palette::LABEL_TEXT,
+                        ..default()
+                    },
+                ))
+                .insert(ObjectiveText);
+        });
+}
+
+fn update_objective(
+    progress: Res<Progress>,
+    inventory: Res<Inventory>,
+    definitions: Res<Assets<ProgressionDefinition>>,
+    handle: Res<DataHandle<ProgressionDefinition>>,
+    mut text: Query<(&mut Text, Ref<ObjectiveText>)>,
+) {
+    let Some(definition) = definitions.get(&handle.0) else {
+        return;
+    };
+    for (mut text, marker) in &mut text {
+        if !progress.is_changed() && !inventory.is_changed() && !marker.is_added() {
+            continue;
+        }
+        text.sections[0].value = definition
+            .objective(&progress, &inventory)
+            .unwrap_or_default()
+            .into();
+    }
+}
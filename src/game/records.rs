@@ -0,0 +1,204 @@
+//! Statistics of the current run and a persistent record of every finished run.
+
+use std::{cmp::Ordering, fs, io, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    screen::{PlayState, Screen},
+    AppSet,
+};
+
+use super::{
+    ghost::GhostTrack,
+    levels::{CurrentLevel, FIRST_LEVEL},
+    logic::{CurrentCycle, YEARS_PER_CYCLE},
+    save::{data_dir, unix_timestamp, DisableSaving, SaveError},
+    speedrun::Split,
+};
+
+/// Bump this whenever [`Records`] changes in a way that old files can't be read anymore.
+pub const RECORDS_VERSION: u32 = 1;
+
+/// Number of runs listed on the summary screen.
+pub const TOP_RUNS: usize = 5;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<RunStats>();
+    app.add_systems(
+        Update,
+        tick_play_time
+            .in_set(AppSet::TickTimers)
+            .run_if(in_state(PlayState::InGame)),
+    );
+    app.add_systems(OnEnter(Screen::Credits), record_run);
+}
+
+/// Statistics of the current run that aren't part of the world state.
+#[derive(Resource, Serialize, Deserialize, Clone, Default, Debug)]
+pub struct RunStats {
+    /// Real time spent in game, in seconds.
+    pub play_time: f32,
+    /// Real time spent fast-forwarding or rewinding with the hourglass, in seconds.
+    pub hourglass_time: f32,
+    #[serde(default)]
+    pub splits: Vec<Split>,
+    /// Stored next to the save rather than in it, see [`super::save`].
+    #[serde(skip)]
+    pub track: GhostTrack,
+}
+
+/// A finished run.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RunRecord {
+    /// Id of the level that was finished.
+    #[serde(default = "first_level")]
+    pub level: String,
+    pub years: usize,
+    pub cycles: usize,
+    pub play_time: f32,
+    pub hourglass_time: f32,
+    /// Seconds since the Unix epoch.
+    pub completed: u64,
+}
+
+fn first_level() -> String {
+    FIRST_LEVEL.into()
+}
+
+impl RunRecord {
+    pub fn used_hourglass(&self) -> bool {
+        self.hourglass_time > 0.0
+    }
+
+    /// Fewer years is better, real time breaks ties.
+    pub fn compare(&self, other: &RunRecord) -> Ordering {
+        self.years
+            .cmp(&other.years)
+            .then(self.play_time.total_cmp(&other.play_time))
+    }
+}
+
+/// All finished runs, as stored on disk.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct Records {
+    pub version: u32,
+    pub runs: Vec<RunRecord>,
+}
+
+impl Records {
+    pub fn load() -> Self {
+        match Self::read() {
+            Ok(records) => records,
+            Err(SaveError::Io(error)) if error.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(error) => {
+                warn!("Ignoring records file: {error}");
+                Self::default()
+            }
+        }
+    }
+
+    fn read() -> Result<Self, SaveError> {
+        let contents = fs::read_to_string(records_path())?;
+        let records: Records = ron::from_str(&contents)?;
+        if records.version != RECORDS_VERSION {
+            return Err(SaveError::UnsupportedVersion(records.version));
+        }
+        Ok(records)
+    }
+
+    pub fn write(&self) -> Result<(), SaveError> {
+        fs::create_dir_all(data_dir())?;
+        let contents = ron::ser::to_string_pretty(self, default())?;
+        fs::write(records_path(), contents)?;
+        Ok(())
+    }
+
+    fn runs_of<'a>(&'a self, level: &'a str) -> impl Iterator<Item = &'a RunRecord> {
+        self.runs.iter().filter(move |run| run.level == level)
+    }
+
+    pub fn has_finished(&self, level: &str) -> bool {
+        self.runs_of(level).next().is_some()
+    }
+
+    pub fn personal_best(&self, level: &str) -> Option<&RunRecord> {
+        self.runs_of(level).min_by(|a, b| a.compare(b))
+    }
+
+    /// The best runs of a level, best first.
+    pub fn top_runs(&self, level: &str, count: usize) -> Vec<&RunRecord> {
+        let mut runs: Vec<_> = self.runs_of(level).collect();
+        runs.sort_by(|a, b| a.compare(b));
+        runs.truncate(count);
+        runs
+    }
+}
+
+fn records_path() -> PathBuf {
+    data_dir().join("records.ron")
+}
+
+/// The run that was just finished, compared against the records before it.
+#[derive(Resource)]
+pub struct LastRun {
+    pub record: RunRecord,
+    pub previous_best: Option<RunRecord>,
+    pub records: Records,
+}
+
+impl LastRun {
+    pub fn is_personal_best(&self) -> bool {
+        self.previous_best
+            .as_ref()
+            .map_or(true, |best| self.record.compare(best) == Ordering::Less)
+    }
+}
+
+/// Format a duration in seconds as `h:mm:ss`, or `m:ss` if it is shorter than an hour.
+pub fn format_duration(seconds: f32) -> String {
+    let seconds = seconds as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+    match hours {
+        0 => format!("{minutes}:{seconds:02}"),
+        _ => format!("{hours}:{minutes:02}:{seconds:02}"),
+    }
+}
+
+fn tick_play_time(mut stats: ResMut<RunStats>, time: Res<Time>) {
+    stats.play_time += time.delta_seconds();
+}
+
+pub fn record_run(
+    mut commands: Commands,
+    current_cycle: Res<CurrentCycle>,
+    current_level: Res<CurrentLevel>,
+    stats: Res<RunStats>,
+    disable_saving: Option<Res<DisableSaving>>,
+) {
+    let record = RunRecord {
+        level: current_level.0.clone(),
+        years: current_cycle.1 * YEARS_PER_CYCLE,
+        cycles: current_cycle.1,
+        play_time: stats.play_time,
+        hourglass_time: stats.hourglass_time,
+        completed: unix_timestamp(),
+    };
+
+    let mut records = Records::load();
+    let previous_best = records.personal_best(&current_level.0).cloned();
+    records.version = RECORDS_VERSION;
+    records.runs.push(record.clone());
+    if disable_saving.is_none() {
+        if let Err(error) = records.write() {
+            warn!("Could not save records: {error}");
+        }
+    }
+
+    commands.insert_resource(LastRun {
+        record,
+        previous_best,
+        records,
+    });
+}
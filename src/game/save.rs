@@ -0,0 +1,437 @@
+//! Persist the state of a run to disk and restore it when the game is entered again.
+//! Saves are written as versioned [RON](https://github.com/ron-rs/ron) files, one per save slot.
+
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use bevy::{prelude::*, utils::SystemTime};
+use serde::{Deserialize, Serialize};
+
+use crate::screen::Screen;
+
+use super::{
+    clock::WorldClock,
+    cycles::Cycles,
+    ghost::GhostTrack,
+    hourglass::RewindCharge,
+    inventory::Inventory,
+    levels::CurrentLevel,
+    logic::{BoatPosition, ConsumedInteractables, CurrentCycle, Cycle, YEARS_PER_CYCLE},
+    progression::Progress,
+    records::RunStats,
+    spawn::player::{CameraPivot, Player},
+};
+
+/// Bump this whenever [`SaveData`] changes in a way that old files can't be read anymore.
+pub const SAVE_VERSION: u32 = 10;
+
+/// Number of save slots offered in the title menu.
+pub const SAVE_SLOTS: usize = 3;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ActiveSlot>();
+    app.observe(save_game);
+    app.add_systems(OnEnter(Screen::Playing), load_game);
+    app.add_systems(OnEnter(Screen::Credits), delete_save);
+    app.add_systems(
+        Update,
+        (restore_player, restore_boat, finish_restore)
+            .chain()
+            .run_if(resource_exists::<PendingRestore>),
+    );
+}
+
+/// Trigger this event to write the current state of the run to disk.
+#[derive(Event)]
+pub struct SaveGame;
+
+/// While this resource exists, nothing about the current run is written to disk.
+#[derive(Resource)]
+pub struct DisableSaving;
+
+/// Insert this resource to start the next run from the given save instead of the active slot.
+#[derive(Resource)]
+pub struct LoadFrom(pub Option<SaveData>);
+
+/// Everything needed to restore a run exactly as it was left.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SaveData {
+    pub version: u32,
+    /// Seconds since the Unix epoch.
+    pub last_played: u64,
+    /// Id of the level the run is played on.
+    pub level: String,
+    pub cycle: Cycle,
+    pub cycles_elapsed: usize,
+    pub day_progress: f32,
+    pub progress: Progress,
+    pub inventory: Inventory,
+    /// Ids of the interactables that were used up.
+    pub consumed: Vec<String>,
+    pub boat_docked_at_island: bool,
+    pub boat_transform: Option<Transform>,
+    pub player_transform: Transform,
+    pub camera_rotation: Quat,
+    pub rewind_charge: RewindCharge,
+    pub stats: RunStats,
+}
+
+impl SaveData {
+    pub fn read(path: &Path) -> Result<Self, SaveError> {
+        let contents = fs::read_to_string(path)?;
+        let data: SaveData = ron::from_str(&contents)?;
+        if data.version != SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion(data.version));
+        }
+        Ok(data)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), SaveError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let contents = ron::ser::to_string_pretty(self, default())?;
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// In-game years that have passed in this run.
+    pub fn years(&self) -> usize {
+        self.cycles_elapsed * YEARS_PER_CYCLE
+    }
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Serialize(ron::Error),
+    Deserialize(ron::error::SpannedError),
+    UnsupportedVersion(u32),
+    Malformed(&'static str),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(error) => write!(f, "{error}"),
+            SaveError::Serialize(error) => write!(f, "{error}"),
+            SaveError::Deserialize(error) => write!(f, "{error}"),
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "save version {version} is not supported (expected {SAVE_VERSION})"
+            ),
+            SaveError::Malformed(reason) => write!(f, "malformed file: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(error: io::Error) -> Self {
+        SaveError::Io(error)
+    }
+}
+
+impl From<ron::Error> for SaveError {
+    fn from(error: ron::Error) -> Self {
+        SaveError::Serialize(error)
+    }
+}
+
+impl From<ron::error::SpannedError> for SaveError {
+    fn from(error: ron::error::SpannedError) -> Self {
+        SaveError::Deserialize(error)
+    }
+}
+
+/// Directory in which all persistent game data is stored.
+pub fn data_dir() -> PathBuf {
+    let base = if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+    base.unwrap_or_else(|| PathBuf::from(".")).join("worlanwv")
+}
+
+pub fn slot_path(slot: usize) -> PathBuf {
+    data_dir().join(format!("slot_{}.ron", slot + 1))
+}
+
+/// Read the save in the given slot, if there is a readable one.
+pub fn read_slot(slot: usize) -> Option<SaveData> {
+    match SaveData::read(&slot_path(slot)) {
+        Ok(data) => Some(data),
+        Err(SaveError::Io(error)) if error.kind() == io::ErrorKind::NotFound => None,
+        Err(error) => {
+            warn!("Ignoring save slot {}: {error}", slot + 1);
+            None
+        }
+    }
+}
+
+/// The ghost track of the run in a slot is kept in a file of its own, so reading a save, e.g. to
+/// list it in the title menu, doesn't have to parse the whole track.
+fn track_path(slot: usize) -> PathBuf {
+    data_dir().join(format!("slot_{}.track.ron", slot + 1))
+}
+
+fn write_track(slot: usize, track: &GhostTrack) -> Result<(), SaveError> {
+    fs::write(track_path(slot), ron::to_string(track)?)?;
+    Ok(())
+}
+
+/// Read the ghost track of the run in the given slot, if there is a readable one.
+fn read_track(slot: usize) -> Option<GhostTrack> {
+    let track = fs::read_to_string(track_path(slot))
+        .map_err(SaveError::from)
+        .and_then(|contents| Ok(ron::from_str(&contents)?));
+    match track {
+        Ok(track) => Some(track),
+        Err(SaveError::Io(error)) if error.kind() == io::ErrorKind::NotFound => None,
+        Err(error) => {
+            warn!("Ignoring ghost track of save slot {}: {error}", slot + 1);
+            None
+        }
+    }
+}
+
+pub fn delete_slot(slot: usize) {
+    for path in [slot_path(slot), track_path(slot)] {
+        if let Err(error) = fs::remove_file(path) {
+            if error.kind() != io::ErrorKind::NotFound {
+                warn!("Could not delete save slot {}: {error}", slot + 1);
+            }
+        }
+    }
+}
+
+/// The slot the current run is saved to.
+#[derive(Resource)]
+pub struct ActiveSlot(pub usize);
+
+impl Default for ActiveSlot {
+    /// Default to the most recently played slot so it can be continued right away.
+    fn default() -> Self {
+        let slot = (0..SAVE_SLOTS)
+            .filter_map(|slot| read_slot(slot).map(|data| (slot, data.last_played)))
+            .max_by_key(|(_, last_played)| *last_played)
+            .map_or(0, |(slot, _)| slot);
+        Self(slot)
+    }
+}
+
+/// Seconds since the Unix epoch.
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Format a Unix timestamp as a UTC date and time, e.g. `2024-08-20 17:05 UTC`.
+pub fn format_timestamp(timestamp: u64) -> String {
+    // Days to civil date conversion from http://howardhinnant.github.io/date_algorithms.html
+    let days = (timestamp / 86_400) as i64 + 719_468;
+    let seconds = timestamp % 86_400;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        seconds / 3_600,
+        seconds % 3_600 / 60
+    )
+}
+
+/// Parts of a save that can only be applied once the entities they belong to exist.
+#[derive(Resource)]
+struct PendingRestore {
+    player: Option<(Transform, Quat)>,
+    boat: Option<Transform>,
+}
+
+fn save_game(
+    _trigger: Trigger<SaveGame>,
+    current_level: Res<CurrentLevel>,
+    current_cycle: Res<CurrentCycle>,
+    clock: Res<WorldClock>,
+    progress: Res<Progress>,
+    inventory: Res<Inventory>,
+    consumed: Res<ConsumedInteractables>,
+    boat_position: Res<BoatPosition>,
+    active_slot: Res<ActiveSlot>,
+    player: Query<(&Transform, &GlobalTransform), With<Player>>,
+    camera_pivot: Query<&Transform, With<CameraPivot>>,
+    boat: Query<&Transform, With<AnimationPlayer>>,
+    rewind_charge: Res<RewindCharge>,
+    stats: Res<RunStats>,
+    disable_saving: Option<Res<DisableSaving>>,
+) {
+    if disable_saving.is_some() {
+        return;
+    }
+    let (Ok((player_transform, player_global)), Ok(camera_pivot)) =
+        (player.get_single(), camera_pivot.get_single())
+    else {
+        return;
+    };
+
+    // While rowing, the player is parented to the boat.
+    let player_transform = match boat_position.currently_rowing {
+        true => player_global.compute_transform(),
+        false => *player_transform,
+    };
+
+    let data = SaveData {
+        version: SAVE_VERSION,
+        last_played: unix_timestamp(),
+        level: current_level.0.clone(),
+        cycle: current_cycle.0.clone(),
+        cycles_elapsed: current_cycle.1,
+        day_progress: clock.progress,
+        progress: progress.clone(),
+        inventory: inventory.clone(),
+        consumed: consumed.0.iter().cloned().collect(),
+        boat_docked_at_island: boat_position.docked_at_island,
+        // The boat scene may not have finished loading yet.
+        boat_transform: boat.get_single().ok().copied(),
+        player_transform,
+        camera_rotation: camera_pivot.rotation,
+        rewind_charge: rewind_charge.clone(),
+        stats: stats.clone(),
+    };
+    let result = data
+        .write(&slot_path(active_slot.0))
+        .and_then(|()| write_track(active_slot.0, &stats.track));
+    if let Err(error) = result {
+        warn!("Could not save game: {error}");
+    }
+}
+
+/// Reset the run and apply the active slot's save on top of it, if there is one.
+pub fn load_game(
+    mut commands: Commands,
+    active_slot: Res<ActiveSlot>,
+    current_level: Res<CurrentLevel>,
+    mut current_cycle: ResMut<CurrentCycle>,
+    mut clock: ResMut<WorldClock>,
+    mut progress: ResMut<Progress>,
+    mut inventory: ResMut<Inventory>,
+    mut consumed: ResMut<ConsumedInteractables>,
+    mut boat_position: ResMut<BoatPosition>,
+    mut rewind_charge: ResMut<RewindCharge>,
+    mut stats: ResMut<RunStats>,
+    load_from: Option<Res<LoadFrom>>,
+    cycles: Res<Cycles>,
+) {
+    *current_cycle = CurrentCycle(cycles.first(), 0);
+    clock.restart(0.0);
+    *progress = Progress::default();
+    *inventory = Inventory::default();
+    consumed.0.clear();
+    boat_position.docked_at_island = false;
+    boat_position.currently_rowing = false;
+    *rewind_charge = RewindCharge::default();
+    *stats = RunStats::default();
+
+    // Saves that don't come from a slot have no ghost track, it is recorded anew from here on.
+    let (data, track) = match load_from {
+        Some(load_from) => {
+            commands.remove_resource::<LoadFrom>();
+            (load_from.0.clone(), None)
+        }
+        None => (read_slot(active_slot.0), Some(active_slot.0)),
+    };
+    let Some(data) = data else {
+        return;
+    };
+    if data.level != current_level.0 {
+        warn!(
+            "Not loading a save of level \"{}\" into level \"{}\"",
+            data.level, current_level.0
+        );
+        return;
+    }
+
+    // The cycle definitions may have changed since the game was saved.
+    let cycle = match cycles.get(&data.cycle) {
+        Some(_) => data.cycle,
+        None => {
+            warn!("Saved cycle \"{}\" doesn't exist anymore", data.cycle.0);
+            cycles.first()
+        }
+    };
+    *current_cycle = CurrentCycle(cycle, data.cycles_elapsed);
+    clock.progress = data.day_progress;
+    *progress = data.progress;
+    *inventory = data.inventory;
+    consumed.0.extend(data.consumed);
+    boat_position.docked_at_island = data.boat_docked_at_island;
+    *rewind_charge = data.rewind_charge;
+    *stats = data.stats;
+    if let Some(track) = track.and_then(read_track) {
+        stats.track = track;
+    }
+    commands.insert_resource(PendingRestore {
+        player: Some((data.player_transform, data.camera_rotation)),
+        boat: data.boat_transform,
+    });
+}
+
+fn restore_player(
+    mut pending: ResMut<PendingRestore>,
+    mut player: Query<&mut Transform, (With<Player>, Without<CameraPivot>)>,
+    mut camera_pivot: Query<&mut Transform, (With<CameraPivot>, Without<Player>)>,
+) {
+    let (Ok(mut player), Ok(mut camera_pivot)) =
+        (player.get_single_mut(), camera_pivot.get_single_mut())
+    else {
+        return;
+    };
+    if let Some((transform, camera_rotation)) = pending.player.take() {
+        *player = transform;
+        camera_pivot.rotation = camera_rotation;
+    }
+}
+
+fn restore_boat(
+    mut pending: ResMut<PendingRestore>,
+    mut boat: Query<&mut Transform, Added<AnimationPlayer>>,
+) {
+    let Ok(mut boat) = boat.get_single_mut() else {
+        return;
+    };
+    if let Some(transform) = pending.boat.take() {
+        *boat = transform;
+    }
+}
+
+fn finish_restore(mut commands: Commands, pending: Res<PendingRestore>) {
+    if pending.player.is_none() && pending.boat.is_none() {
+        commands.remove_resource::<PendingRestore>();
+    }
+}
+
+/// A finished run starts from scratch next time.
+fn delete_save(active_slot: Res<ActiveSlot>, disable_saving: Option<Res<DisableSaving>>) {
+    if disable_saving.is_none() {
+        delete_slot(active_slot.0);
+    }
+}
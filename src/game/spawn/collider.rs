@@ -0,0 +1,183 @@
+//! Colliders for level meshes. Which shape a mesh gets is chosen per object with the `collider`
+//! tag (see [`super::markers`]). Trimeshes match the geometry exactly, but they are expensive and
+//! make it easy to get stuck in, so prefer convex shapes wherever they fit well enough.
+
+use std::fmt;
+
+use bevy::{prelude::*, render::primitives::Aabb};
+use bevy_rapier3d::{
+    dynamics::RigidBody,
+    geometry::{Collider, ComputedColliderShape},
+    prelude::{ActiveCollisionTypes, GravityScale},
+};
+use serde::Deserialize;
+
+use super::{
+    heightfield::{heightfield_from_mesh, HeightfieldError},
+    level::Terrain,
+    markers::read_markers,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    // TODO: Do this once after loading geometry, don't check every frame
+    app.add_systems(Update, spawn_colliders.after(read_markers));
+}
+
+/// Which kind of collider to build from a level mesh.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ColliderKind {
+    /// The exact triangles of the mesh.
+    #[serde(rename = "trimesh")]
+    TriMesh,
+    /// A grid of heights, for terrain.
+    Heightfield,
+    /// The smallest convex shape that contains the mesh.
+    ConvexHull,
+    /// Several convex shapes that approximate the mesh, computed with V-HACD.
+    #[serde(alias = "vhacd")]
+    ConvexDecomposition,
+    /// A box that fits the mesh's bounding box.
+    Box,
+    /// A capsule along the longest side of the mesh's bounding box.
+    Capsule,
+    /// A ball that fits inside the mesh's bounding box.
+    Ball,
+}
+
+impl ColliderKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ColliderKind::TriMesh => "trimesh",
+            ColliderKind::Heightfield => "heightfield",
+            ColliderKind::ConvexHull => "convex hull",
+            ColliderKind::ConvexDecomposition => "convex decomposition",
+            ColliderKind::Box => "box",
+            ColliderKind::Capsule => "capsule",
+            ColliderKind::Ball => "ball",
+        }
+    }
+}
+
+/// Build a collider from this entity's mesh.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LevelCollider {
+    pub kind: ColliderKind,
+    /// Whether the mesh is only there to shape the collider and should not be rendered.
+    pub collider_only: bool,
+}
+
+#[derive(Debug)]
+pub enum ColliderError {
+    /// The mesh asset isn't loaded.
+    MissingMesh,
+    /// The mesh has no vertex positions.
+    NoVertices,
+    /// Rapier could not build the shape, e.g. because the mesh isn't a triangle list
+    /// or is too flat for a convex hull.
+    Unsupported(ColliderKind),
+    Heightfield(HeightfieldError),
+}
+
+impl fmt::Display for ColliderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColliderError::MissingMesh => write!(f, "mesh is not loaded"),
+            ColliderError::NoVertices => write!(f, "mesh has no vertices"),
+            ColliderError::Unsupported(kind) => {
+                write!(f, "could not compute a {} from the mesh", kind.name())
+            }
+            ColliderError::Heightfield(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+/// Build a collider of the given kind from a mesh, in the mesh's local space.
+pub fn build_collider(kind: ColliderKind, mesh: &Mesh) -> Result<Collider, ColliderError> {
+    let computed = |shape: ComputedColliderShape| {
+        Collider::from_bevy_mesh(mesh, &shape).ok_or(ColliderError::Unsupported(kind))
+    };
+    match kind {
+        ColliderKind::TriMesh => computed(ComputedColliderShape::TriMesh),
+        ColliderKind::ConvexHull => computed(ComputedColliderShape::ConvexHull),
+        ColliderKind::ConvexDecomposition => {
+            computed(ComputedColliderShape::ConvexDecomposition(default()))
+        }
+        ColliderKind::Heightfield => heightfield_from_mesh(mesh)
+            .map(|heightfield| heightfield.collider())
+            .map_err(ColliderError::Heightfield),
+        ColliderKind::Box | ColliderKind::Capsule | ColliderKind::Ball => {
+            let aabb = mesh.compute_aabb().ok_or(ColliderError::NoVertices)?;
+            Ok(fit_to_aabb(kind, aabb))
+        }
+    }
+}
+
+fn fit_to_aabb(kind: ColliderKind, aabb: Aabb) -> Collider {
+    let center = Vec3::from(aabb.center);
+    let half_extents = Vec3::from(aabb.half_extents);
+    let shape = match kind {
+        ColliderKind::Ball => Collider::ball(half_extents.min_element()),
+        ColliderKind::Capsule => {
+            // The two shorter sides determine the radius, the longest one the axis.
+            let axis = match half_extents.max_element() {
+                length if length == half_extents.x => Vec3::X,
+                length if length == half_extents.y => Vec3::Y,
+                _ => Vec3::Z,
+            };
+            let radius = (half_extents * (Vec3::ONE - axis)).max_element();
+            let half_height = (half_extents.dot(axis) - radius).max(0.0);
+            Collider::capsule(-axis * half_height, axis * half_height, radius)
+        }
+        _ => Collider::cuboid(half_extents.x, half_extents.y, half_extents.z),
+    };
+    match center == Vec3::ZERO {
+        true => shape,
+        false => Collider::compound(vec![(center, Quat::IDENTITY, shape)]),
+    }
+}
+
+fn spawn_colliders(
+    mut commands: Commands,
+    objects: Query<(Entity, &Name, &LevelCollider, &Handle<Mesh>), Added<LevelCollider>>,
+    meshes: Res<Assets<Mesh>>,
+) {
+    for (entity, name, level_collider, mesh) in &objects {
+        let mesh = meshes.get(mesh);
+        let collider = match mesh
+            .ok_or(ColliderError::MissingMesh)
+            .and_then(|mesh| build_collider(level_collider.kind, mesh))
+        {
+            Ok(collider) => collider,
+            Err(error) => {
+                let kind = level_collider.kind.name();
+                // Something to stand on is better than falling through the level.
+                match mesh.and_then(|mesh| build_collider(ColliderKind::Box, mesh).ok()) {
+                    Some(fallback) => {
+                        warn!(
+                            "No {kind} collider for \"{name}\" ({error}), using its bounding box"
+                        );
+                        fallback
+                    }
+                    None => {
+                        warn!("No {kind} collider for \"{name}\": {error}");
+                        continue;
+                    }
+                }
+            }
+        };
+
+        commands
+            .entity(entity)
+            .insert(RigidBody::Fixed)
+            .insert(GravityScale(0.0))
+            .insert(ActiveCollisionTypes::all())
+            .insert(collider);
+        if level_collider.kind == ColliderKind::Heightfield {
+            commands.entity(entity).insert(Terrain);
+        }
+        if level_collider.collider_only {
+            commands.entity(entity).remove::<Handle<Mesh>>();
+        }
+    }
+}
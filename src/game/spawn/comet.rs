@@ -0,0 +1,70 @@
+//! Spawn the comet, with a glowing head and a tail that grow as it approaches.
+
+use bevy::{pbr::NotShadowCaster, prelude::*, render::view::NoFrustumCulling};
+
+use crate::screen::Screen;
+
+pub(super) fn plugin(app: &mut App) {
+    app.observe(spawn_comet);
+}
+
+/// Spawn the comet at its resting place, where it is at the start of the first cycle.
+#[derive(Event, Debug)]
+pub struct SpawnComet(pub Transform);
+
+#[derive(Component, Debug)]
+pub struct Comet {
+    /// Where the comet starts its approach.
+    pub rest: Transform,
+}
+
+/// Glow around the comet, brighter the closer it is.
+#[derive(Component, Debug)]
+pub struct CometGlow;
+
+/// Tail of the comet, a cylinder along its local Y axis with a length of 1.
+#[derive(Component, Debug)]
+pub struct CometTail;
+
+fn spawn_comet(
+    trigger: Trigger<SpawnComet>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    asset_server: Res<AssetServer>,
+) {
+    let rest = trigger.event().0;
+    let glow = StandardMaterial {
+        base_color: Color::srgba(1.0, 0.8, 0.6, 0.3),
+        emissive: LinearRgba::BLACK,
+        alpha_mode: AlphaMode::Add,
+        unlit: true,
+        ..default()
+    };
+    commands
+        .spawn(SceneBundle {
+            scene: asset_server.load(GltfAssetLabel::Scene(0).from_asset("models/comet.glb")),
+            transform: rest,
+            ..default()
+        })
+        .insert((Name::new("Comet"), StateScoped(Screen::Playing)))
+        .insert(Comet { rest })
+        .with_children(|comet| {
+            comet
+                .spawn(MaterialMeshBundle {
+                    mesh: meshes.add(Sphere::new(8.0)),
+                    material: materials.add(glow.clone()),
+                    ..default()
+                })
+                .insert((Name::new("Comet glow"), CometGlow))
+                .insert((NotShadowCaster, NoFrustumCulling));
+            comet
+                .spawn(MaterialMeshBundle {
+                    mesh: meshes.add(Cylinder::new(3.0, 1.0)),
+                    material: materials.add(glow),
+                    ..default()
+                })
+                .insert((Name::new("Comet tail"), CometTail))
+                .insert((NotShadowCaster, NoFrustumCulling));
+        });
+}
@@ -0,0 +1,125 @@
+//! Only the scene of the current cycle exists in the world. When the cycle changes, the previous
+//! scene is despawned and the next one is spawned in its place, while the scenes that may follow
+//! are loaded in the background so the switch doesn't wait for the disk.
+//!
+//! Changes that persist across cycles, like the planted tree or the finished monument, are not
+//! part of the scenes. They are recorded in the [`Progress`] and applied again as the objects of a
+//! new scene instance are added. The scene stays hidden until that has happened, so the unchanged
+//! island never shows up for a frame.
+//!
+//! The markers of the level overlay are spawned into the scene together with it.
+
+use bevy::{prelude::*, scene::SceneInstanceReady};
+
+use crate::{
+    game::{
+        cycles::Cycles,
+        data::DataHandle,
+        logic::{CurrentCycle, Cycle},
+        progression::{apply_object_effects, Progress, MONUMENT_FLAG},
+    },
+    screen::Screen,
+};
+
+use super::overlay::LevelOverlay;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<PreloadedCycleScenes>();
+    app.observe(spawn_cycle_scene);
+    app.add_systems(
+        Update,
+        (
+            reveal_cycle_scenes.after(apply_object_effects),
+            preload_cycle_scenes.run_if(in_state(Screen::Playing)),
+        ),
+    );
+}
+
+/// Trigger this event to replace the scene of the current cycle with the given cycle's.
+#[derive(Event, Debug)]
+pub struct SpawnCycleScene(pub Cycle);
+
+/// Root of the scene of the cycle it is on.
+#[derive(Component, Debug)]
+pub struct CycleScene;
+
+/// Handles to the scenes of the cycles that may come next, which keep them loaded.
+#[derive(Resource, Default, Debug)]
+struct PreloadedCycleScenes(Vec<Handle<Scene>>);
+
+fn cycle_scene(
+    asset_server: &AssetServer,
+    cycles: &Cycles,
+    cycle: &Cycle,
+) -> Option<Handle<Scene>> {
+    let definition = cycles.get(cycle)?;
+    Some(asset_server.load(GltfAssetLabel::Scene(0).from_asset(definition.scene.clone())))
+}
+
+fn spawn_cycle_scene(
+    trigger: Trigger<SpawnCycleScene>,
+    mut commands: Commands,
+    scenes: Query<Entity, With<CycleScene>>,
+    asset_server: Res<AssetServer>,
+    cycles: Res<Cycles>,
+    overlays: Res<Assets<LevelOverlay>>,
+    overlay_handle: Res<DataHandle<LevelOverlay>>,
+) {
+    for scene in &scenes {
+        commands.entity(scene).despawn_recursive();
+    }
+
+    let cycle = &trigger.event().0;
+    let Some(scene) = cycle_scene(&asset_server, &cycles, cycle) else {
+        warn!("There is no cycle called \"{}\"", cycle.0);
+        return;
+    };
+    commands
+        .spawn(SceneBundle {
+            scene,
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .insert(CycleScene)
+        .insert(cycle.clone())
+        .insert(StateScoped(Screen::Playing))
+        .with_children(|scene| {
+            if let Some(overlay) = overlays.get(&overlay_handle.0) {
+                overlay.spawn_markers(scene, cycle);
+            }
+        });
+}
+
+fn reveal_cycle_scenes(
+    mut events: EventReader<SceneInstanceReady>,
+    mut scenes: Query<&mut Visibility, With<CycleScene>>,
+) {
+    for event in events.read() {
+        if let Ok(mut visibility) = scenes.get_mut(event.parent) {
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+fn preload_cycle_scenes(
+    current_cycle: Res<CurrentCycle>,
+    progress: Res<Progress>,
+    cycles: Res<Cycles>,
+    asset_server: Res<AssetServer>,
+    mut preloaded: ResMut<PreloadedCycleScenes>,
+) {
+    if !current_cycle.is_changed() && !progress.is_changed() && !cycles.is_changed() {
+        return;
+    }
+    // Respawning moves on to the next cycle even after the monument is finished.
+    let mut upcoming = vec![
+        cycles.next(&current_cycle.0),
+        cycles.after_day(&current_cycle.0, progress.has_flag(MONUMENT_FLAG)),
+    ];
+    upcoming.dedup();
+    preloaded.0 = upcoming
+        .iter()
+        .filter(|cycle| **cycle != current_cycle.0)
+        .filter_map(|cycle| cycle_scene(&asset_server, &cycles, cycle))
+        .collect();
+}
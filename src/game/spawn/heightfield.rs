@@ -0,0 +1,335 @@
+//! Turn a terrain mesh into the data of a heightfield collider.
+//!
+//! The mesh has to be a regular grid when seen from above, but it may be rectangular, off-centre
+//! and contain the same vertex more than once, as exporters do along UV seams and split normals.
+//! The node's transform is applied to the collider by Rapier, so only the mesh itself matters here.
+
+use std::fmt;
+
+use bevy::prelude::*;
+use bevy_rapier3d::geometry::Collider;
+
+/// Vertices closer than this fraction of the terrain's size are considered the same.
+const RELATIVE_TOLERANCE: f32 = 1e-4;
+
+/// A heightfield in the terrain mesh's local space.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heightfield {
+    /// Heights in column-major order: one column per vertex along X, one row per vertex along Z.
+    pub heights: Vec<f32>,
+    pub num_rows: usize,
+    pub num_cols: usize,
+    /// Size of the grid along X and Z.
+    pub scale: Vec3,
+    /// Centre of the grid, which Rapier heightfields are placed around.
+    pub center: Vec3,
+}
+
+impl Heightfield {
+    pub fn collider(self) -> Collider {
+        let heightfield =
+            Collider::heightfield(self.heights, self.num_rows, self.num_cols, self.scale);
+        match self.center == Vec3::ZERO {
+            true => heightfield,
+            false => Collider::compound(vec![(self.center, Quat::IDENTITY, heightfield)]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeightfieldError {
+    NoVertices,
+    /// At least two distinct vertices are needed along each axis.
+    TooSmall {
+        columns: usize,
+        rows: usize,
+    },
+    UnevenSpacing {
+        axis: char,
+    },
+    /// The mesh folds over itself, e.g. an overhang or a wall.
+    ConflictingHeights {
+        x: f32,
+        z: f32,
+    },
+    /// The grid has a hole.
+    MissingVertex {
+        x: f32,
+        z: f32,
+    },
+}
+
+impl fmt::Display for HeightfieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeightfieldError::NoVertices => write!(f, "mesh has no vertex positions"),
+            HeightfieldError::TooSmall { columns, rows } => write!(
+                f,
+                "mesh is not a grid: it needs at least 2×2 vertices, found {columns}×{rows}"
+            ),
+            HeightfieldError::UnevenSpacing { axis } => write!(
+                f,
+                "mesh is not a grid: vertices are not evenly spaced along {axis}"
+            ),
+            HeightfieldError::ConflictingHeights { x, z } => write!(
+                f,
+                "mesh is not a heightfield: there are several heights at x = {x}, z = {z}"
+            ),
+            HeightfieldError::MissingVertex { x, z } => write!(
+                f,
+                "mesh is not a grid: there is no vertex at x = {x}, z = {z}"
+            ),
+        }
+    }
+}
+
+pub fn heightfield_from_mesh(mesh: &Mesh) -> Result<Heightfield, HeightfieldError> {
+    let positions = mesh
+        .attribute(Mesh::ATTRIBUTE_POSITION)
+        .and_then(|positions| positions.as_float3())
+        .ok_or(HeightfieldError::NoVertices)?;
+    heightfield_from_positions(positions)
+}
+
+pub fn heightfield_from_positions(positions: &[[f32; 3]]) -> Result<Heightfield, HeightfieldError> {
+    if positions.is_empty() {
+        return Err(HeightfieldError::NoVertices);
+    }
+    let (min, max) = positions.iter().fold(
+        (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+        |(min, max), &position| {
+            let position = Vec3::from_array(position);
+            (min.min(position), max.max(position))
+        },
+    );
+    let size = max - min;
+    let tolerance = size.x.max(size.z) * RELATIVE_TOLERANCE;
+
+    let columns = grid_lines(positions.iter().map(|position| position[0]), tolerance);
+    let rows = grid_lines(positions.iter().map(|position| position[2]), tolerance);
+    if columns.len() < 2 || rows.len() < 2 {
+        return Err(HeightfieldError::TooSmall {
+            columns: columns.len(),
+            rows: rows.len(),
+        });
+    }
+    let spacing = Vec2::new(
+        size.x / (columns.len() - 1) as f32,
+        size.z / (rows.len() - 1) as f32,
+    );
+    let is_even = |lines: &[f32], start: f32, spacing: f32| {
+        lines
+            .iter()
+            .enumerate()
+            .all(|(index, line)| (line - (start + index as f32 * spacing)).abs() <= spacing / 4.0)
+    };
+    if !is_even(&columns, min.x, spacing.x) {
+        return Err(HeightfieldError::UnevenSpacing { axis: 'X' });
+    }
+    if !is_even(&rows, min.z, spacing.y) {
+        return Err(HeightfieldError::UnevenSpacing { axis: 'Z' });
+    }
+
+    let (num_cols, num_rows) = (columns.len(), rows.len());
+    let mut heights: Vec<Option<f32>> = vec![None; num_cols * num_rows];
+    for &[x, y, z] in positions {
+        let column = ((x - min.x) / spacing.x).round() as usize;
+        let row = ((z - min.z) / spacing.y).round() as usize;
+        match &mut heights[row + column * num_rows] {
+            Some(height) if (*height - y).abs() > tolerance.max(f32::EPSILON) => {
+                return Err(HeightfieldError::ConflictingHeights { x, z });
+            }
+            Some(_) => {}
+            height => *height = Some(y),
+        }
+    }
+    let heights = heights
+        .into_iter()
+        .enumerate()
+        .map(|(index, height)| {
+            height.ok_or_else(|| HeightfieldError::MissingVertex {
+                x: columns[index / num_rows],
+                z: rows[index % num_rows],
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Heightfield {
+        heights,
+        num_rows,
+        num_cols,
+        scale: Vec3::new(size.x, 1.0, size.z),
+        center: Vec3::new(min.x + size.x / 2.0, 0.0, min.z + size.z / 2.0),
+    })
+}
+
+/// Sorted, deduplicated coordinates along one axis.
+fn grid_lines(coordinates: impl Iterator<Item = f32>, tolerance: f32) -> Vec<f32> {
+    let mut coordinates: Vec<f32> = coordinates.collect();
+    coordinates.sort_by(f32::total_cmp);
+    coordinates.dedup_by(|next, line| *next - *line <= tolerance);
+    coordinates
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::render::{mesh::PrimitiveTopology, render_asset::RenderAssetUsages};
+
+    use super::*;
+
+    /// A grid of `columns`×`rows` vertices, `spacing` apart, starting at `origin`,
+    /// with every inner vertex duplicated like along a UV seam.
+    fn grid_mesh(
+        columns: usize,
+        rows: usize,
+        spacing: Vec2,
+        origin: Vec2,
+        height: impl Fn(usize, usize) -> f32,
+    ) -> Mesh {
+        let mut positions = vec![];
+        for row in 0..rows {
+            for column in 0..columns {
+                let position = [
+                    origin.x + column as f32 * spacing.x,
+                    height(column, row),
+                    origin.y + row as f32 * spacing.y,
+                ];
+                positions.push(position);
+                if column > 0 && column < columns - 1 {
+                    positions.push(position);
+                }
+            }
+        }
+        // Mix up the order, exporters don't sort vertices either.
+        positions.reverse();
+        let middle = positions.len() / 3;
+        positions.rotate_left(middle);
+        mesh_from_positions(positions)
+    }
+
+    fn mesh_from_positions(positions: Vec<[f32; 3]>) -> Mesh {
+        Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::default(),
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    }
+
+    #[test]
+    fn centred_square_grid() {
+        let mesh = grid_mesh(
+            3,
+            3,
+            Vec2::splat(50.0),
+            Vec2::splat(-50.0),
+            |column, row| (column * 3 + row) as f32,
+        );
+        let heightfield = heightfield_from_mesh(&mesh).unwrap();
+
+        assert_eq!((heightfield.num_cols, heightfield.num_rows), (3, 3));
+        assert_eq!(heightfield.scale, Vec3::new(100.0, 1.0, 100.0));
+        assert_eq!(heightfield.center, Vec3::ZERO);
+        assert_eq!(
+            heightfield.heights,
+            [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]
+        );
+    }
+
+    #[test]
+    fn rectangular_off_centre_grid() {
+        let mesh = grid_mesh(
+            4,
+            2,
+            Vec2::new(2.0, 5.0),
+            Vec2::new(10.0, -20.0),
+            |column, row| column as f32 - row as f32 * 10.0,
+        );
+        let heightfield = heightfield_from_mesh(&mesh).unwrap();
+
+        assert_eq!((heightfield.num_cols, heightfield.num_rows), (4, 2));
+        assert_eq!(heightfield.scale, Vec3::new(6.0, 1.0, 5.0));
+        assert_eq!(heightfield.center, Vec3::new(13.0, 0.0, -17.5));
+        // One column after another, each going along Z.
+        assert_eq!(
+            heightfield.heights,
+            [0.0, -10.0, 1.0, -9.0, 2.0, -8.0, 3.0, -7.0]
+        );
+    }
+
+    #[test]
+    fn tolerates_rounding_errors() {
+        let mut positions = vec![];
+        for row in 0..3 {
+            for column in 0..3 {
+                let jitter = if (row + column) % 2 == 0 { 1e-6 } else { -1e-6 };
+                positions.push([column as f32 * 0.1 + jitter, 1.0, row as f32 * 0.1 - jitter]);
+            }
+        }
+        let heightfield = heightfield_from_mesh(&mesh_from_positions(positions)).unwrap();
+        assert_eq!((heightfield.num_cols, heightfield.num_rows), (3, 3));
+    }
+
+    #[test]
+    fn rejects_uneven_spacing() {
+        let positions = vec![
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [3.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [1.0, 0.0, 1.0],
+            [3.0, 0.0, 1.0],
+        ];
+        assert_eq!(
+            heightfield_from_mesh(&mesh_from_positions(positions)),
+            Err(HeightfieldError::UnevenSpacing { axis: 'X' })
+        );
+    }
+
+    #[test]
+    fn rejects_holes() {
+        let mut positions = vec![];
+        for row in 0..3 {
+            for column in 0..3 {
+                if (column, row) != (1, 2) {
+                    positions.push([column as f32, 0.0, row as f32]);
+                }
+            }
+        }
+        assert_eq!(
+            heightfield_from_mesh(&mesh_from_positions(positions)),
+            Err(HeightfieldError::MissingVertex { x: 1.0, z: 2.0 })
+        );
+    }
+
+    #[test]
+    fn rejects_overhangs() {
+        let mesh = grid_mesh(2, 2, Vec2::ONE, Vec2::ZERO, |_, _| 0.0);
+        let mut positions = mesh
+            .attribute(Mesh::ATTRIBUTE_POSITION)
+            .unwrap()
+            .as_float3()
+            .unwrap()
+            .to_vec();
+        positions.push([1.0, 3.0, 1.0]);
+        assert_eq!(
+            heightfield_from_mesh(&mesh_from_positions(positions)),
+            Err(HeightfieldError::ConflictingHeights { x: 1.0, z: 1.0 })
+        );
+    }
+
+    #[test]
+    fn rejects_meshes_without_area() {
+        let line = vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [2.0, 0.0, 0.0]];
+        assert_eq!(
+            heightfield_from_mesh(&mesh_from_positions(line)),
+            Err(HeightfieldError::TooSmall {
+                columns: 3,
+                rows: 1
+            })
+        );
+        assert_eq!(
+            heightfield_from_mesh(&mesh_from_positions(vec![])),
+            Err(HeightfieldError::NoVertices)
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Interactables are defined in `assets/data/island.interactables.ron` rather than in code,
+//! so objects can be added and tweaked without recompiling. Each definition names the marker
+//! objects in the level it is attached to, the model and collider to spawn there, the prompt
+//! shown when it is looked at and the action that runs when it is used. Interactables that are
+//! part of the puzzle complete steps of the progression instead (see [`crate::game::progression`]).
+
+use bevy::{asset::LoadContext, prelude::*};
+use bevy_rapier3d::prelude::{Collider, ColliderDisabled, CollisionGroups, Group};
+use serde::Deserialize;
+
+use crate::game::{
+    data::{init_data_file, unique_ids, DataAsset, DataError},
+    logic::{on_boat_used, on_game_finished, ConsumedInteractables, Interactable},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    init_data_file::<InteractableDefinitions>(app);
+    app.observe(spawn_interactable);
+    app.observe(consume_interactable);
+}
+
+/// All interactables of the island.
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct InteractableDefinitions {
+    pub interactables: Vec<InteractableDefinition>,
+}
+
+impl InteractableDefinitions {
+    /// The definition attached to a level object with the given name, if any.
+    pub fn for_marker(&self, name: &str) -> Option<&InteractableDefinition> {
+        self.interactables
+            .iter()
+            .find(|definition| name.contains(&definition.marker))
+    }
+
+    pub fn get(&self, id: &str) -> Option<&InteractableDefinition> {
+        self.interactables
+            .iter()
+            .find(|definition| definition.id == id)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct InteractableDefinition {
+    /// Unique name that remembers the interactable in saves.
+    pub id: String,
+    /// Level objects without custom properties whose name contains this get the interactable attached.
+    pub marker: String,
+    /// Path of the glTF file, relative to the assets folder.
+    pub model: String,
+    pub collider: ColliderShape,
+    #[serde(default)]
+    pub collision_groups: CollisionGroupsDefinition,
+    pub prompt: String,
+    /// Conditions that all have to be met before the interactable can be used.
+    #[serde(default)]
+    pub requires: Vec<Requirement>,
+    /// Shown instead of the prompt while the requirements are not met.
+    #[serde(default)]
+    pub locked_prompt: String,
+    #[serde(default)]
+    pub action: Option<InteractionAction>,
+    #[serde(default)]
+    pub consume: Consume,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ColliderShape {
+    Ball {
+        radius: f32,
+    },
+    /// Half the size along each axis.
+    Cuboid {
+        x: f32,
+        y: f32,
+        z: f32,
+    },
+    CapsuleY {
+        half_height: f32,
+        radius: f32,
+    },
+}
+
+impl ColliderShape {
+    pub fn collider(self) -> Collider {
+        match self {
+            ColliderShape::Ball { radius } => Collider::ball(radius),
+            ColliderShape::Cuboid { x, y, z } => Collider::cuboid(x, y, z),
+            ColliderShape::CapsuleY {
+                half_height,
+                radius,
+            } => Collider::capsule_y(half_height, radius),
+        }
+    }
+}
+
+/// Groups are numbered from 1 to 32. Interactables must be members of group 2,
+/// as that is what the player's view is checked against.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct CollisionGroupsDefinition {
+    pub memberships: Vec<u32>,
+    pub filters: GroupFilter,
+}
+
+impl Default for CollisionGroupsDefinition {
+    fn default() -> Self {
+        Self {
+            memberships: vec![2],
+            filters: GroupFilter::All,
+        }
+    }
+}
+
+impl CollisionGroupsDefinition {
+    pub fn collision_groups(&self) -> CollisionGroups {
+        let filters = match &self.filters {
+            GroupFilter::All => Group::ALL,
+            GroupFilter::Only(groups) => groups_from_numbers(groups),
+            GroupFilter::AllExcept(groups) => Group::ALL & !groups_from_numbers(groups),
+        };
+        CollisionGroups::new(groups_from_numbers(&self.memberships), filters)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub enum GroupFilter {
+    All,
+    Only(Vec<u32>),
+    AllExcept(Vec<u32>),
+}
+
+fn groups_from_numbers(numbers: &[u32]) -> Group {
+    numbers
+        .iter()
+        .filter(|number| (1..=32).contains(*number))
+        .fold(Group::NONE, |groups, number| {
+            groups | Group::from_bits_truncate(1 << (number - 1))
+        })
+}
+
+/// A condition for using an interactable.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub enum Requirement {
+    /// The player carries the item with this id.
+    HasItem(String),
+    /// The progression flag is set.
+    Flag(String),
+    /// The cycle with this id is the current one.
+    Cycle(String),
+    /// The time of day is in this window, as a fraction of the day.
+    /// The window wraps around if `from` is greater than `to`.
+    TimeOfDay { from: f32, to: f32 },
+}
+
+/// What happens when the player uses an interactable.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InteractionAction {
+    UseBoat,
+    FinishGame,
+}
+
+impl InteractionAction {
+    fn observe(self, entity: &mut EntityCommands) {
+        match self {
+            InteractionAction::UseBoat => entity.observe(on_boat_used),
+            InteractionAction::FinishGame => entity.observe(on_game_finished),
+        };
+    }
+}
+
+/// Whether an interactable is used up when it completes a step of the progression,
+/// and how it comes back when the level is spawned again afterwards.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Consume {
+    #[default]
+    Never,
+    /// It stays in the level but can't be used anymore.
+    Disable,
+    /// It is gone for the rest of the run.
+    Despawn,
+}
+
+impl DataAsset for InteractableDefinitions {
+    const EXTENSION: &'static str = "interactables.ron";
+
+    fn validate(&mut self, _load_context: &mut LoadContext<'_>) -> Result<(), DataError> {
+        let ids = self
+            .interactables
+            .iter()
+            .map(|definition| definition.id.as_str());
+        unique_ids("interactable", ids)?;
+        Ok(())
+    }
+}
+
+/// Trigger this event to attach an interactable to a level object.
+#[derive(Event)]
+pub struct SpawnInteractable(pub InteractableDefinition, pub Entity);
+
+/// Id of the definition an interactable was spawned from.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct InteractableId(pub String);
+
+/// Marks interactables that can be used up.
+#[derive(Component)]
+struct Consumable(Consume);
+
+/// Trigger this event on an interactable to use it up.
+#[derive(Event, Debug)]
+pub struct ConsumeInteractable;
+
+fn spawn_interactable(
+    trigger: Trigger<SpawnInteractable>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    consumed: Res<ConsumedInteractables>,
+) {
+    let SpawnInteractable(definition, parent) = trigger.event();
+    let consumed = consumed.0.contains(&definition.id);
+    if consumed && definition.consume == Consume::Despawn {
+        return;
+    }
+    commands.entity(*parent).with_children(|parent| {
+        let mut interactable = parent.spawn(SceneBundle {
+            scene: asset_server.load(GltfAssetLabel::Scene(0).from_asset(definition.model.clone())),
+            ..default()
+        });
+        interactable
+            .insert(
+                Interactable::new(definition.prompt.clone()).with_requirements(
+                    definition.requires.clone(),
+                    definition.locked_prompt.clone(),
+                ),
+            )
+            .insert(InteractableId(definition.id.clone()))
+            .insert(definition.collider.collider())
+            .insert(definition.collision_groups.collision_groups());
+        if let Some(action) = definition.action {
+            action.observe(&mut interactable);
+        }
+        if definition.consume != Consume::Never {
+            interactable.insert(Consumable(definition.consume));
+        }
+        if consumed {
+            interactable.insert(ColliderDisabled);
+        }
+    });
+}
+
+fn consume_interactable(
+    trigger: Trigger<ConsumeInteractable>,
+    mut commands: Commands,
+    consumables: Query<(&InteractableId, &Consumable)>,
+    mut consumed: ResMut<ConsumedInteractables>,
+) {
+    let entity = trigger.entity();
+    let Ok((id, consumable)) = consumables.get(entity) else {
+        return;
+    };
+    consumed.0.insert(id.0.clone());
+    match consumable.0 {
+        Consume::Never => {}
+        Consume::Disable => {
+            commands.entity(entity).insert(ColliderDisabled);
+        }
+        Consume::Despawn => commands.entity(entity).despawn_recursive(),
+    }
+}
@@ -0,0 +1,245 @@
+//! Level objects are tagged with custom properties in Blender, which are exported as glTF extras:
+//!
+//! ```json
+//! {"collider": "trimesh", "spawn": "boat", "progress_object": "TreeUpper"}
+//! ```
+//!
+//! The tags are read into typed components as soon as a scene is spawned. Objects without any
+//! custom properties fall back to being matched by their name. Spawn points that the level
+//! overlay has taken over are skipped (see [`super::overlay`]).
+
+use bevy::{
+    gltf::{GltfExtras, GltfMeshExtras},
+    prelude::*,
+};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::game::{
+    data::DataHandle,
+    logic::{BoatPosition, Cycle, Interactable},
+};
+
+use super::{
+    collider::{ColliderKind, LevelCollider},
+    cycle_scene::CycleScene,
+    interactable::{InteractableDefinitions, InteractionAction, SpawnInteractable},
+    overlay::{LevelOverlay, PromptOverride},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (read_markers, (spawn_at_markers, attach_highlights)).chain(),
+    );
+}
+
+/// Attach the interactable with this id here.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct SpawnPoint(pub String);
+
+/// A mesh that is shown while the interactable it belongs to is looked at.
+#[derive(Component, Debug)]
+pub struct HighlightMesh;
+
+/// The id that the puzzle steps refer to a level object with, for objects that have custom
+/// properties. `None` if it has some, but no id. Objects without any are referred to by name.
+#[derive(Component, Debug, Clone, PartialEq, Eq)]
+pub struct ProgressObject(pub Option<String>);
+
+/// Tags of a single level object.
+#[derive(Default, Debug, PartialEq)]
+pub struct LevelMarkers {
+    pub collider: Option<LevelCollider>,
+    pub spawn: Option<String>,
+    pub highlight: bool,
+    pub progress_object: Option<String>,
+}
+
+impl LevelMarkers {
+    /// Read the tags from the JSON object of a glTF extras field.
+    /// Anything that isn't understood is skipped and reported in the returned warnings.
+    pub fn from_extras(extras: &str) -> (Self, Vec<String>) {
+        let mut markers = LevelMarkers::default();
+        let mut warnings = vec![];
+        let properties: Map<String, Value> = match serde_json::from_str(extras) {
+            Ok(properties) => properties,
+            Err(error) => return (markers, vec![format!("invalid custom properties: {error}")]),
+        };
+
+        let mut collider_only = false;
+        for (key, value) in &properties {
+            let result = match key.as_str() {
+                "collider" => parse(value).map(|kind| {
+                    markers.collider = Some(LevelCollider {
+                        kind,
+                        collider_only: false,
+                    })
+                }),
+                "collider_only" => parse(value).map(|value| collider_only = value),
+                "spawn" => parse(value).map(|id| markers.spawn = Some(id)),
+                "highlight" => parse(value).map(|value| markers.highlight = value),
+                "progress_object" => parse(value).map(|id| markers.progress_object = Some(id)),
+                _ => Err(format!("unknown tag \"{key}\"")),
+            };
+            if let Err(warning) = result {
+                warnings.push(warning);
+            }
+        }
+        if let Some(collider) = &mut markers.collider {
+            collider.collider_only = collider_only;
+        } else if collider_only {
+            warnings.push("\"collider_only\" has no effect without \"collider\"".into());
+        }
+        (markers, warnings)
+    }
+
+    /// Guess the tags from the object's name, the way levels were tagged before they had extras.
+    pub fn from_name(name: &str, definitions: Option<&InteractableDefinitions>) -> Self {
+        if name.contains("highlight") {
+            return LevelMarkers {
+                highlight: true,
+                ..default()
+            };
+        }
+        let collider = name.contains("_col").then(|| LevelCollider {
+            kind: match name.contains("terrain") {
+                true => ColliderKind::Heightfield,
+                false => ColliderKind::TriMesh,
+            },
+            collider_only: name.contains("_colonly"),
+        });
+        LevelMarkers {
+            collider,
+            spawn: definitions
+                .and_then(|definitions| definitions.for_marker(name))
+                .map(|definition| definition.id.clone()),
+            highlight: false,
+            progress_object: None,
+        }
+    }
+}
+
+fn parse<T: for<'de> Deserialize<'de>>(value: &Value) -> Result<T, String> {
+    T::deserialize(value).map_err(|error| format!("invalid value {value}: {error}"))
+}
+
+pub fn read_markers(
+    mut commands: Commands,
+    objects: Query<
+        (
+            Entity,
+            &Name,
+            Option<&GltfExtras>,
+            Option<&GltfMeshExtras>,
+            Option<&Parent>,
+            Has<Handle<Mesh>>,
+        ),
+        Added<Name>,
+    >,
+    node_extras: Query<&GltfExtras>,
+    ancestors: Query<&Parent>,
+    scene_cycles: Query<&Cycle, With<CycleScene>>,
+    definitions: Res<Assets<InteractableDefinitions>>,
+    definitions_handle: Res<DataHandle<InteractableDefinitions>>,
+    overlays: Res<Assets<LevelOverlay>>,
+    overlay_handle: Res<DataHandle<LevelOverlay>>,
+) {
+    let definitions = definitions.get(&definitions_handle.0);
+    let overlay = overlays.get(&overlay_handle.0);
+    for (entity, name, extras, mesh_extras, parent, has_mesh) in &objects {
+        // Blender puts custom properties of an object on its node,
+        // while the meshes are spawned as children of it.
+        let parent_extras = parent
+            .filter(|_| has_mesh)
+            .and_then(|parent| node_extras.get(parent.get()).ok());
+        let extras = extras
+            .map(|extras| &extras.value)
+            .or(mesh_extras.map(|extras| &extras.value));
+        let markers = match (extras, parent_extras) {
+            (Some(extras), _) => {
+                let (markers, warnings) = LevelMarkers::from_extras(extras);
+                for warning in warnings {
+                    warn!("Level object \"{name}\": {warning}");
+                }
+                markers
+            }
+            (None, Some(parent_extras)) => {
+                let parent = LevelMarkers::from_extras(&parent_extras.value).0;
+                LevelMarkers {
+                    collider: parent.collider,
+                    progress_object: parent.progress_object,
+                    ..default()
+                }
+            }
+            (None, None) => LevelMarkers::from_name(name.as_str(), definitions),
+        };
+
+        let mut entity = commands.entity(entity);
+        if extras.is_some() || parent_extras.is_some() {
+            entity.insert(ProgressObject(markers.progress_object));
+        }
+        if let Some(collider) = markers.collider {
+            entity.insert(collider);
+        }
+        if let Some(id) = markers.spawn {
+            let cycle = ancestors
+                .iter_ancestors(entity.id())
+                .find_map(|ancestor| scene_cycles.get(ancestor).ok());
+            if !overlay.is_some_and(|overlay| overlay.replaces(name, cycle)) {
+                entity.insert(SpawnPoint(id));
+            }
+        }
+        if markers.highlight {
+            entity.insert(HighlightMesh);
+        }
+    }
+}
+
+fn spawn_at_markers(
+    mut commands: Commands,
+    spawn_points: Query<
+        (Entity, &SpawnPoint, &Transform, Option<&PromptOverride>),
+        Added<SpawnPoint>,
+    >,
+    mut boat_position: ResMut<BoatPosition>,
+    definitions: Res<Assets<InteractableDefinitions>>,
+    definitions_handle: Res<DataHandle<InteractableDefinitions>>,
+) {
+    // The loading screen waits for the definitions, so they are only missing if they failed to load.
+    let Some(definitions) = definitions.get(&definitions_handle.0) else {
+        return;
+    };
+    for (entity, spawn_point, transform, prompt) in &spawn_points {
+        let Some(definition) = definitions.get(&spawn_point.0) else {
+            warn!("There is no interactable called \"{}\"", spawn_point.0);
+            continue;
+        };
+        if definition.action == Some(InteractionAction::UseBoat) {
+            boat_position.initial_transform = *transform;
+        }
+        let mut definition = definition.clone();
+        if let Some(prompt) = prompt {
+            definition.prompt.clone_from(&prompt.0);
+        }
+        commands.trigger(SpawnInteractable(definition, entity));
+    }
+}
+
+fn attach_highlights(
+    mut commands: Commands,
+    highlights: Query<Entity, Added<HighlightMesh>>,
+    parents: Query<&Parent>,
+    mut interactables: Query<&mut Interactable>,
+) {
+    for entity in &highlights {
+        let Some(mut interactable) = parents
+            .iter_ancestors(entity)
+            .find_map(|ancestor| interactables.get_mut(ancestor).ok())
+        else {
+            continue;
+        };
+        interactable.highlight_mesh = Some(entity);
+        commands.entity(entity).insert(Visibility::Hidden);
+    }
+}
@@ -4,9 +4,25 @@
 
 use bevy::prelude::*;
 
+pub mod collider;
+pub mod comet;
+pub mod cycle_scene;
+pub mod heightfield;
+pub mod interactable;
 pub mod level;
+pub mod markers;
+pub mod overlay;
 pub mod player;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_plugins((level::plugin, player::plugin));
+    app.add_plugins((
+        collider::plugin,
+        comet::plugin,
+        cycle_scene::plugin,
+        interactable::plugin,
+        level::plugin,
+        markers::plugin,
+        overlay::plugin,
+        player::plugin,
+    ));
 }
@@ -0,0 +1,116 @@
+//! The level overlay in `assets/data/island.overlay.ron` places interactables on top of the glTF
+//! scenes, so they can be moved around without re-exporting the level. It is written by the level
+//! editor in dev builds, but can be edited by hand as well.
+//!
+//! Overlay markers are spawned into the scene of every cycle they belong to. A level object with
+//! the same name as an overlay marker no longer spawns its interactable, so the overlay can move
+//! interactables that are placed in Blender, too.
+
+use bevy::{asset::LoadContext, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    data::{init_data_file, unique_ids, DataAsset, DataError},
+    logic::Cycle,
+};
+
+use super::markers::SpawnPoint;
+
+pub(super) fn plugin(app: &mut App) {
+    init_data_file::<LevelOverlay>(app);
+}
+
+#[derive(Asset, TypePath, Serialize, Deserialize, Debug, Clone, Default)]
+pub struct LevelOverlay {
+    pub markers: Vec<OverlayMarker>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OverlayMarker {
+    /// Unique name of the marker. A level object with this name is no longer a spawn point.
+    pub name: String,
+    /// Id of the interactable that is spawned at the marker.
+    pub interactable: String,
+    /// The cycle whose scene the marker is part of, or every cycle if there is none.
+    #[serde(default)]
+    pub cycle: Option<String>,
+    pub translation: [f32; 3],
+    /// Rotation around the vertical axis, in degrees.
+    #[serde(default)]
+    pub yaw: f32,
+    /// Shown instead of the prompt of the interactable's definition.
+    #[serde(default)]
+    pub prompt: Option<String>,
+}
+
+impl OverlayMarker {
+    /// Where the marker is, relative to the scene it is part of.
+    pub fn transform(&self) -> Transform {
+        Transform::from_translation(Vec3::from_array(self.translation))
+            .with_rotation(Quat::from_rotation_y(self.yaw.to_radians()))
+    }
+
+    pub fn is_in(&self, cycle: Option<&Cycle>) -> bool {
+        match (&self.cycle, cycle) {
+            (None, _) => true,
+            (Some(own), Some(cycle)) => *own == cycle.0,
+            (Some(_), None) => false,
+        }
+    }
+}
+
+impl LevelOverlay {
+    pub fn get(&self, name: &str) -> Option<&OverlayMarker> {
+        self.markers.iter().find(|marker| marker.name == name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut OverlayMarker> {
+        self.markers.iter_mut().find(|marker| marker.name == name)
+    }
+
+    /// Whether a level object in the scene of the given cycle is replaced by an overlay marker.
+    pub fn replaces(&self, name: &str, cycle: Option<&Cycle>) -> bool {
+        self.get(name).is_some_and(|marker| marker.is_in(cycle))
+    }
+
+    /// Spawn the markers of a cycle as children of its scene.
+    pub fn spawn_markers(&self, scene: &mut ChildBuilder, cycle: &Cycle) {
+        for marker in &self.markers {
+            if marker.is_in(Some(cycle)) {
+                spawn_marker(scene, marker);
+            }
+        }
+    }
+}
+
+/// A spawn point that comes from the overlay rather than the level.
+#[derive(Component, Debug)]
+pub struct OverlaySpawnPoint;
+
+/// Shown instead of the prompt of the interactable that is spawned at this marker.
+#[derive(Component, Debug, Clone)]
+pub struct PromptOverride(pub String);
+
+pub fn spawn_marker(scene: &mut ChildBuilder, marker: &OverlayMarker) -> Entity {
+    let mut entity = scene.spawn(SpatialBundle::from_transform(marker.transform()));
+    entity
+        .insert(Name::new(marker.name.clone()))
+        .insert(SpawnPoint(marker.interactable.clone()))
+        .insert(OverlaySpawnPoint);
+    if let Some(prompt) = &marker.prompt {
+        entity.insert(PromptOverride(prompt.clone()));
+    }
+    entity.id()
+}
+
+impl DataAsset for LevelOverlay {
+    const EXTENSION: &'static str = "overlay.ron";
+
+    fn validate(&mut self, _load_context: &mut LoadContext<'_>) -> Result<(), DataError> {
+        unique_ids(
+            "marker",
+            self.markers.iter().map(|marker| marker.name.as_str()),
+        )?;
+        Ok(())
+    }
+}
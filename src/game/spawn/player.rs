@@ -1,7 +1,5 @@
 //! Spawn the player.
 
-use std::f32::consts::PI;
-
 use bevy::{
     core_pipeline::{bloom::BloomSettings, experimental::taa::TemporalAntiAliasBundle},
     pbr::{ScreenSpaceAmbientOcclusionBundle, ScreenSpaceAmbientOcclusionSettings},
@@ -24,7 +22,11 @@ pub(super) fn plugin(app: &mut App) {
 }
 
 #[derive(Event, Debug)]
-pub struct SpawnPlayer;
+pub struct SpawnPlayer {
+    pub translation: Vec3,
+    /// Direction the player looks at, in degrees around the vertical axis.
+    pub yaw: f32,
+}
 
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default, Reflect)]
 #[reflect(Component)]
@@ -37,10 +39,11 @@ pub struct PlayerCamera;
 pub struct CameraPivot;
 
 fn spawn_player(
-    _trigger: Trigger<SpawnPlayer>,
+    trigger: Trigger<SpawnPlayer>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
 ) {
+    let spawn = trigger.event();
     commands
         .spawn((
             Name::new("Player"),
@@ -49,7 +52,7 @@ fn spawn_player(
             StateScoped(Screen::Playing),
             SpatialBundle {
                 transform: Transform {
-                    translation: Vec3::new(5.52, 4.4, -33.66),
+                    translation: spawn.translation,
                     ..default()
                 },
                 ..default()
@@ -82,7 +85,7 @@ fn spawn_player(
                 .insert(SpatialBundle {
                     transform: Transform {
                         translation: Vec3::new(0.0, 0.8, 0.0),
-                        rotation: Quat::from_euler(EulerRot::YXZ, PI - 0.2, 0.2, 0.0),
+                        rotation: Quat::from_euler(EulerRot::YXZ, spawn.yaw.to_radians(), 0.2, 0.0),
                         ..default()
                     },
                     ..default()
@@ -0,0 +1,339 @@
+//! Drive a running [LiveSplit](https://livesplit.org/) instance through its LiveSplit Server
+//! component, which accepts one text command per line over TCP.
+//!
+//! The client is disabled by default. To enable it, create `livesplit.ron` in the data directory:
+//!
+//! ```ron
+//! (enabled: true, host: "localhost", port: 16834)
+//! ```
+
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{self, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    game::{logic::CycleChanged, save::data_dir},
+    screen::{PlayState, Screen},
+};
+
+use super::MilestoneReached;
+
+/// How long to wait before the first reconnection attempt. Doubles with every failed attempt.
+const MIN_RETRY_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+/// Commands sent while LiveSplit is unreachable are kept up to this number.
+const MAX_PENDING: usize = 64;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Startup, connect);
+    app.add_systems(OnEnter(Screen::Playing), reset_timer);
+    app.add_systems(OnEnter(PlayState::InGame), start_or_resume_timer);
+    app.add_systems(OnEnter(PlayState::InMenu), pause_timer);
+    app.observe(split_on_milestone);
+    app.observe(split_on_cycle_changed);
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct LiveSplitSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+}
+
+impl Default for LiveSplitSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".into(),
+            port: 16834,
+        }
+    }
+}
+
+impl LiveSplitSettings {
+    pub fn load() -> Self {
+        let contents = match fs::read_to_string(settings_path()) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(error) => {
+                warn!("Could not read LiveSplit settings: {error}");
+                return Self::default();
+            }
+        };
+        ron::from_str(&contents).unwrap_or_else(|error| {
+            warn!("Ignoring LiveSplit settings: {error}");
+            Self::default()
+        })
+    }
+
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+fn settings_path() -> PathBuf {
+    data_dir().join("livesplit.ron")
+}
+
+/// Commands of the LiveSplit Server protocol used by the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiveSplitCommand {
+    StartTimer,
+    Split,
+    Pause,
+    Resume,
+    Reset,
+}
+
+impl LiveSplitCommand {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LiveSplitCommand::StartTimer => "starttimer",
+            LiveSplitCommand::Split => "split",
+            LiveSplitCommand::Pause => "pause",
+            LiveSplitCommand::Resume => "resume",
+            LiveSplitCommand::Reset => "reset",
+        }
+    }
+}
+
+/// A connection to LiveSplit Server that queues commands while it is unreachable
+/// and reconnects with an increasing delay.
+pub struct LiveSplitClient {
+    address: String,
+    stream: Option<TcpStream>,
+    pending: VecDeque<LiveSplitCommand>,
+    retry_delay: Duration,
+    next_attempt: Instant,
+}
+
+impl LiveSplitClient {
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            stream: None,
+            pending: VecDeque::new(),
+            retry_delay: MIN_RETRY_DELAY,
+            next_attempt: Instant::now(),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    pub fn send(&mut self, command: LiveSplitCommand) {
+        if self.pending.len() == MAX_PENDING {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(command);
+        self.flush();
+    }
+
+    /// Deliver pending commands, connecting first if necessary and the retry delay has passed.
+    pub fn flush(&mut self) {
+        if self.stream.is_none() {
+            if self.pending.is_empty() || Instant::now() < self.next_attempt {
+                return;
+            }
+            match self.connect() {
+                Ok(stream) => {
+                    info!("Connected to LiveSplit Server at {}", self.address);
+                    self.stream = Some(stream);
+                    self.retry_delay = MIN_RETRY_DELAY;
+                }
+                Err(error) => {
+                    debug!("Could not connect to LiveSplit Server: {error}");
+                    self.schedule_retry();
+                    return;
+                }
+            }
+        }
+
+        while let Some(&command) = self.pending.front() {
+            let Some(stream) = &mut self.stream else {
+                return;
+            };
+            let line = format!("{}\r\n", command.as_str());
+            if let Err(error) = stream.write_all(line.as_bytes()) {
+                warn!("Lost connection to LiveSplit Server: {error}");
+                self.stream = None;
+                self.schedule_retry();
+                return;
+            }
+            self.pending.pop_front();
+        }
+    }
+
+    fn connect(&self) -> io::Result<TcpStream> {
+        let mut last_error = None;
+        for address in self.address.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+                Ok(stream) => {
+                    stream.set_nodelay(true)?;
+                    return Ok(stream);
+                }
+                Err(error) => last_error = Some(error),
+            }
+        }
+        Err(last_error
+            .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "address did not resolve")))
+    }
+
+    fn schedule_retry(&mut self) {
+        self.next_attempt = Instant::now() + self.retry_delay;
+        self.retry_delay = (self.retry_delay * 2).min(MAX_RETRY_DELAY);
+    }
+}
+
+/// Handle to the thread that owns the [`LiveSplitClient`], so the game never waits on the network.
+#[derive(Resource)]
+pub struct LiveSplit {
+    commands: Sender<LiveSplitCommand>,
+    /// Whether the timer was started for the world that is currently loaded.
+    running: bool,
+}
+
+impl LiveSplit {
+    pub fn spawn(address: String) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::Builder::new()
+            .name("LiveSplit client".into())
+            .spawn(move || run_client(LiveSplitClient::new(address), receiver))
+            .expect("failed to spawn the LiveSplit client thread");
+        Self {
+            commands: sender,
+            running: false,
+        }
+    }
+
+    fn send(&self, command: LiveSplitCommand) {
+        // The client thread only stops once this sender is dropped.
+        let _ = self.commands.send(command);
+    }
+}
+
+fn run_client(mut client: LiveSplitClient, commands: Receiver<LiveSplitCommand>) {
+    loop {
+        match commands.recv_timeout(MIN_RETRY_DELAY) {
+            Ok(command) => client.send(command),
+            Err(RecvTimeoutError::Timeout) => client.flush(),
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+fn connect(mut commands: Commands) {
+    let settings = LiveSplitSettings::load();
+    if settings.enabled {
+        commands.insert_resource(LiveSplit::spawn(settings.address()));
+    }
+}
+
+fn reset_timer(live_split: Option<ResMut<LiveSplit>>) {
+    let Some(mut live_split) = live_split else {
+        return;
+    };
+    if live_split.running {
+        live_split.send(LiveSplitCommand::Reset);
+        live_split.running = false;
+    }
+}
+
+fn start_or_resume_timer(live_split: Option<ResMut<LiveSplit>>) {
+    let Some(mut live_split) = live_split else {
+        return;
+    };
+    if live_split.running {
+        live_split.send(LiveSplitCommand::Resume);
+    } else {
+        live_split.send(LiveSplitCommand::Reset);
+        live_split.send(LiveSplitCommand::StartTimer);
+        live_split.running = true;
+    }
+}
+
+fn pause_timer(live_split: Option<Res<LiveSplit>>) {
+    if let Some(live_split) = live_split.filter(|live_split| live_split.running) {
+        live_split.send(LiveSplitCommand::Pause);
+    }
+}
+
+fn split_on_milestone(_trigger: Trigger<MilestoneReached>, live_split: Option<Res<LiveSplit>>) {
+    if let Some(live_split) = live_split.filter(|live_split| live_split.running) {
+        live_split.send(LiveSplitCommand::Split);
+    }
+}
+
+fn split_on_cycle_changed(_trigger: Trigger<CycleChanged>, live_split: Option<Res<LiveSplit>>) {
+    if let Some(live_split) = live_split.filter(|live_split| live_split.running) {
+        live_split.send(LiveSplitCommand::Split);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    fn read_lines(listener: &TcpListener, count: usize) -> Vec<String> {
+        let (stream, _) = listener.accept().unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        BufReader::new(stream)
+            .lines()
+            .take(count)
+            .map(Result::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn sends_commands_as_lines() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = LiveSplitClient::new(listener.local_addr().unwrap().to_string());
+
+        client.send(LiveSplitCommand::StartTimer);
+        client.send(LiveSplitCommand::Split);
+        client.send(LiveSplitCommand::Pause);
+        assert!(client.is_connected());
+
+        assert_eq!(read_lines(&listener, 3), ["starttimer", "split", "pause"]);
+    }
+
+    #[test]
+    fn queues_commands_until_the_server_is_reachable() {
+        // Find a free port, then close it again so that connecting fails.
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let mut client = LiveSplitClient::new(address.to_string());
+        client.send(LiveSplitCommand::StartTimer);
+        client.send(LiveSplitCommand::Split);
+        assert!(!client.is_connected());
+
+        let listener = TcpListener::bind(address).unwrap();
+        client.next_attempt = Instant::now();
+        client.flush();
+        assert!(client.is_connected());
+
+        assert_eq!(read_lines(&listener, 2), ["starttimer", "split"]);
+    }
+}
@@ -0,0 +1,509 @@
+//! Reading and writing [LiveSplit](https://livesplit.org/) `.lss` splits files.
+//! Only the parts of the format the game cares about are read. When a file that was read is
+//! written again, everything else in it, like the attempt history, icons or auto splitter
+//! settings, is kept as it was.
+
+use std::{
+    fmt::{self, Write},
+    ops::Range,
+};
+
+/// A run as stored in a splits file.
+#[derive(Debug, Clone, Default)]
+pub struct LssRun {
+    pub attempt_count: u32,
+    pub segments: Vec<LssSegment>,
+    /// The file the run was read from.
+    source: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LssSegment {
+    pub name: String,
+    /// Time at which this segment was split in the personal best.
+    pub personal_best: Option<SegmentTime>,
+    /// Fastest time this segment alone was ever completed in.
+    pub best_segment: Option<SegmentTime>,
+    /// Contents of the `<Segment>` element the segment was read from.
+    source: Option<String>,
+}
+
+impl LssSegment {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            personal_best: None,
+            best_segment: None,
+            source: None,
+        }
+    }
+}
+
+/// Times in seconds. The game time holds the in-game years, so that one year is shown as one
+/// second in LiveSplit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SegmentTime {
+    pub real_time: f32,
+    pub game_time: Option<f32>,
+}
+
+#[derive(Debug)]
+pub struct LssError(&'static str);
+
+impl fmt::Display for LssError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid splits file: {}", self.0)
+    }
+}
+
+impl std::error::Error for LssError {}
+
+impl LssRun {
+    pub fn parse(xml: &str) -> Result<Self, LssError> {
+        let run = element(xml, "Run").ok_or(LssError("missing <Run>"))?;
+        let attempt_count = element(run, "AttemptCount")
+            .and_then(|count| count.trim().parse().ok())
+            .unwrap_or(0);
+        let segments = element(run, "Segments").ok_or(LssError("missing <Segments>"))?;
+        let segments = elements(segments, "Segment")
+            .into_iter()
+            .map(|segment| LssSegment {
+                name: element(segment.contents, "Name")
+                    .map(unescape)
+                    .unwrap_or_default(),
+                personal_best: element(segment.contents, "SplitTimes").and_then(|split_times| {
+                    elements(split_times, "SplitTime")
+                        .into_iter()
+                        .find(is_personal_best)
+                        .and_then(|time| parse_segment_time(time.contents))
+                }),
+                best_segment: element(segment.contents, "BestSegmentTime")
+                    .and_then(parse_segment_time),
+                source: Some(segment.contents.into()),
+            })
+            .collect();
+        Ok(Self {
+            attempt_count,
+            segments,
+            source: Some(xml.into()),
+        })
+    }
+
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+        // Writing to a `String` can't fail.
+        let _ = match &self.source {
+            Some(source) => self.patch_xml(&mut xml, source),
+            None => self.write_xml(&mut xml),
+        };
+        xml
+    }
+
+    fn write_xml(&self, xml: &mut String) -> fmt::Result {
+        writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(xml, r#"<Run version="1.7.0">"#)?;
+        writeln!(xml, "  <GameIcon />")?;
+        writeln!(xml, "  <GameName>Worlanwv</GameName>")?;
+        writeln!(xml, "  <CategoryName>Any%</CategoryName>")?;
+        writeln!(xml, "  <Offset>00:00:00</Offset>")?;
+        writeln!(xml, "  <AttemptCount>{}</AttemptCount>", self.attempt_count)?;
+        writeln!(xml, "  <AttemptHistory />")?;
+        writeln!(xml, "  <Segments>")?;
+        for segment in &self.segments {
+            segment.write_xml(xml)?;
+        }
+        writeln!(xml, "  </Segments>")?;
+        writeln!(xml, "  <AutoSplitterSettings />")?;
+        writeln!(xml, "</Run>")
+    }
+
+    /// Write the file the run was read from, with the attempt count and segments replaced.
+    fn patch_xml(&self, xml: &mut String, source: &str) -> fmt::Result {
+        let attempt_count = format!("<AttemptCount>{}</AttemptCount>", self.attempt_count);
+        let source = replace_element(source, "AttemptCount", |_| true, &attempt_count)
+            .unwrap_or_else(|| source.into());
+        let Some(segments) = elements(&source, "Segments")
+            .first()
+            .map(|segments| segments.span.clone())
+        else {
+            return self.write_xml(xml);
+        };
+        xml.push_str(&source[..segments.start]);
+        writeln!(xml, "<Segments>")?;
+        for segment in &self.segments {
+            segment.write_xml(xml)?;
+        }
+        write!(xml, "  </Segments>")?;
+        xml.push_str(&source[segments.end..]);
+        Ok(())
+    }
+}
+
+impl LssSegment {
+    fn write_xml(&self, xml: &mut String) -> fmt::Result {
+        if let Some(patched) = self
+            .source
+            .as_deref()
+            .and_then(|source| self.patch_xml(source))
+        {
+            return writeln!(xml, "    <Segment>{patched}</Segment>");
+        }
+        writeln!(xml, "    <Segment>")?;
+        writeln!(xml, "      <Name>{}</Name>", escape(&self.name))?;
+        writeln!(xml, "      <Icon />")?;
+        writeln!(xml, "      <SplitTimes>")?;
+        write!(xml, "        ")?;
+        write_personal_best(xml, self.personal_best)?;
+        writeln!(xml, "      </SplitTimes>")?;
+        write!(xml, "      ")?;
+        write_best_segment(xml, self.best_segment)?;
+        writeln!(xml, "      <SegmentHistory />")?;
+        writeln!(xml, "    </Segment>")
+    }
+
+    /// The segment the way it was read, with the personal best and best segment replaced.
+    /// `None` if either of them can't be found, e.g. because the file was edited by hand.
+    fn patch_xml(&self, source: &str) -> Option<String> {
+        let mut personal_best = String::new();
+        let mut best_segment = String::new();
+        write_personal_best(&mut personal_best, self.personal_best).ok()?;
+        write_best_segment(&mut best_segment, self.best_segment).ok()?;
+        let source = replace_element(source, "SplitTime", is_personal_best, personal_best.trim())?;
+        replace_element(&source, "BestSegmentTime", |_| true, best_segment.trim())
+    }
+}
+
+/// Write the personal best split time of a segment, indented for its place in the file.
+fn write_personal_best(xml: &mut String, time: Option<SegmentTime>) -> fmt::Result {
+    let Some(time) = time else {
+        return writeln!(xml, r#"<SplitTime name="Personal Best" />"#);
+    };
+    writeln!(xml, r#"<SplitTime name="Personal Best">"#)?;
+    write_segment_time(xml, time, "          ")?;
+    writeln!(xml, "        </SplitTime>")
+}
+
+/// Write the best segment time of a segment, indented for its place in the file.
+fn write_best_segment(xml: &mut String, time: Option<SegmentTime>) -> fmt::Result {
+    let Some(time) = time else {
+        return writeln!(xml, "<BestSegmentTime />");
+    };
+    writeln!(xml, "<BestSegmentTime>")?;
+    write_segment_time(xml, time, "        ")?;
+    writeln!(xml, "      </BestSegmentTime>")
+}
+
+fn write_segment_time(xml: &mut String, time: SegmentTime, indent: &str) -> fmt::Result {
+    writeln!(
+        xml,
+        "{indent}<RealTime>{}</RealTime>",
+        format_time(time.real_time)
+    )?;
+    if let Some(game_time) = time.game_time {
+        writeln!(
+            xml,
+            "{indent}<GameTime>{}</GameTime>",
+            format_time(game_time)
+        )?;
+    }
+    Ok(())
+}
+
+fn parse_segment_time(xml: &str) -> Option<SegmentTime> {
+    Some(SegmentTime {
+        real_time: parse_time(element(xml, "RealTime")?)?,
+        game_time: element(xml, "GameTime").and_then(parse_time),
+    })
+}
+
+/// Format seconds the way LiveSplit does, e.g. `01:02:03.4500000`.
+pub fn format_time(seconds: f32) -> String {
+    let ticks = (seconds.max(0.0) as f64 * 10_000_000.0).round() as u64;
+    let (seconds, fraction) = (ticks / 10_000_000, ticks % 10_000_000);
+    format!(
+        "{:02}:{:02}:{:02}.{fraction:07}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+/// Parse a LiveSplit time span such as `1.02:03:04.5`, `02:03:04` or `-00:00:01.25`.
+pub fn parse_time(text: &str) -> Option<f32> {
+    let text = text.trim();
+    let (sign, text) = match text.strip_prefix('-') {
+        Some(text) => (-1.0, text),
+        None => (1.0, text),
+    };
+    let mut parts = text.rsplitn(3, ':');
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    let minutes: f64 = match parts.next() {
+        Some(minutes) => minutes.parse().ok()?,
+        None => 0.0,
+    };
+    let hours: f64 = match parts.next() {
+        Some(hours) => match hours.split_once('.') {
+            Some((days, hours)) => days.parse::<f64>().ok()? * 24.0 + hours.parse::<f64>().ok()?,
+            None => hours.parse().ok()?,
+        },
+        None => 0.0,
+    };
+    Some((sign * (hours * 3600.0 + minutes * 60.0 + seconds)) as f32)
+}
+
+/// An element found by [`elements`].
+struct Element<'a> {
+    attributes: &'a str,
+    contents: &'a str,
+    /// Where the whole element is in the searched text, tags included.
+    span: Range<usize>,
+}
+
+/// Every `<tag>` element in `xml`, in order. Self-closing elements have empty contents.
+/// Elements may not contain an element of the same name.
+fn elements<'a>(xml: &'a str, tag: &str) -> Vec<Element<'a>> {
+    let open = format!("<{tag}");
+    let close = format!("</{tag}>");
+    let mut found = vec![];
+    let mut offset = 0;
+    while let Some(start) = xml[offset..].find(&open).map(|start| offset + start) {
+        let after = start + open.len();
+        // Skip tags that merely start with the name we are looking for.
+        if !xml[after..].starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/') {
+            offset = after;
+            continue;
+        }
+        let Some(tag_end) = xml[after..].find('>').map(|end| after + end) else {
+            break;
+        };
+        let attributes = &xml[after..tag_end];
+        if let Some(attributes) = attributes.strip_suffix('/') {
+            found.push(Element {
+                attributes,
+                contents: "",
+                span: start..tag_end + 1,
+            });
+            offset = tag_end + 1;
+            continue;
+        }
+        let body = tag_end + 1;
+        let Some(body_end) = xml[body..].find(&close).map(|end| body + end) else {
+            break;
+        };
+        let end = body_end + close.len();
+        found.push(Element {
+            attributes,
+            contents: &xml[body..body_end],
+            span: start..end,
+        });
+        offset = end;
+    }
+    found
+}
+
+fn element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    elements(xml, tag).first().map(|element| element.contents)
+}
+
+/// Replace the first `<tag>` element that `matches` with `replacement`, if there is one.
+fn replace_element(
+    xml: &str,
+    tag: &str,
+    matches: impl Fn(&Element) -> bool,
+    replacement: &str,
+) -> Option<String> {
+    let span = elements(xml, tag).into_iter().find(matches)?.span;
+    Some([&xml[..span.start], replacement, &xml[span.end..]].concat())
+}
+
+fn is_personal_best(split_time: &Element) -> bool {
+    split_time.attributes.contains(r#"name="Personal Best""#)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A splits file as LiveSplit writes it, cut down to two segments.
+    const LIVESPLIT_FILE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Run version="1.7.0">
+  <GameIcon><![CDATA[iVBORw0KGgo=]]></GameIcon>
+  <GameName>Worlanwv</GameName>
+  <CategoryName>Glitchless</CategoryName>
+  <Metadata>
+    <Run id="" />
+    <Variables />
+  </Metadata>
+  <Offset>00:00:00</Offset>
+  <AttemptCount>3</AttemptCount>
+  <AttemptHistory>
+    <Attempt id="1" started="08/20/2024 17:00:00" ended="08/20/2024 17:05:00">
+      <RealTime>00:05:00.0000000</RealTime>
+    </Attempt>
+  </AttemptHistory>
+  <Segments>
+    <Segment>
+      <Name>Hourglass</Name>
+      <Icon />
+      <SplitTimes>
+        <SplitTime name="Personal Best">
+          <RealTime>00:01:02.5000000</RealTime>
+          <GameTime>00:00:40</GameTime>
+        </SplitTime>
+      </SplitTimes>
+      <BestSegmentTime>
+        <RealTime>00:01:00</RealTime>
+      </BestSegmentTime>
+      <SegmentHistory>
+        <Time id="1">
+          <RealTime>00:01:02.5000000</RealTime>
+        </Time>
+      </SegmentHistory>
+    </Segment>
+    <Segment>
+      <Name>Sapling &amp; Tree</Name>
+      <Icon />
+      <SplitTimes>
+        <SplitTime name="Balanced PB">
+          <RealTime>00:02:00</RealTime>
+        </SplitTime>
+        <SplitTime name="Personal Best" />
+      </SplitTimes>
+      <BestSegmentTime />
+      <SegmentHistory />
+    </Segment>
+  </Segments>
+  <AutoSplitterSettings>
+    <Split>True</Split>
+  </AutoSplitterSettings>
+</Run>
+"#;
+
+    fn time(real_time: f32, game_time: Option<f32>) -> Option<SegmentTime> {
+        Some(SegmentTime {
+            real_time,
+            game_time,
+        })
+    }
+
+    #[test]
+    fn parses_livesplit_file() {
+        let run = LssRun::parse(LIVESPLIT_FILE).unwrap();
+        assert_eq!(run.attempt_count, 3);
+        assert_eq!(run.segments.len(), 2);
+        assert_eq!(run.segments[0].name, "Hourglass");
+        assert_eq!(run.segments[0].personal_best, time(62.5, Some(40.0)));
+        assert_eq!(run.segments[0].best_segment, time(60.0, None));
+        assert_eq!(run.segments[1].name, "Sapling & Tree");
+        assert_eq!(run.segments[1].personal_best, None);
+        assert_eq!(run.segments[1].best_segment, None);
+    }
+
+    #[test]
+    fn keeps_unknown_parts_when_written_again() {
+        let mut run = LssRun::parse(LIVESPLIT_FILE).unwrap();
+        run.attempt_count = 4;
+        run.segments[1].personal_best = time(120.0, Some(80.0));
+        run.segments[1].best_segment = time(57.5, Some(40.0));
+        let xml = run.to_xml();
+
+        for kept in [
+            "<CategoryName>Glitchless</CategoryName>",
+            "<GameIcon><![CDATA[iVBORw0KGgo=]]></GameIcon>",
+            r#"<Attempt id="1" started="08/20/2024 17:00:00" ended="08/20/2024 17:05:00">"#,
+            r#"<Time id="1">"#,
+            r#"<SplitTime name="Balanced PB">"#,
+            "<Split>True</Split>",
+        ] {
+            assert!(xml.contains(kept), "{kept} is missing from:\n{xml}");
+        }
+        let written = LssRun::parse(&xml).unwrap();
+        assert_eq!(written.attempt_count, 4);
+        assert_eq!(written.segments[0].personal_best, time(62.5, Some(40.0)));
+        assert_eq!(written.segments[0].best_segment, time(60.0, None));
+        assert_eq!(written.segments[1].personal_best, time(120.0, Some(80.0)));
+        assert_eq!(written.segments[1].best_segment, time(57.5, Some(40.0)));
+    }
+
+    #[test]
+    fn round_trips_new_run() {
+        let run = LssRun {
+            attempt_count: 2,
+            segments: vec![
+                LssSegment {
+                    personal_best: time(90.25, Some(300.0)),
+                    best_segment: time(45.0, None),
+                    ..LssSegment::new("Monument <finished>")
+                },
+                LssSegment::new("Empty"),
+            ],
+            ..Default::default()
+        };
+        let written = LssRun::parse(&run.to_xml()).unwrap();
+        assert_eq!(written.attempt_count, 2);
+        assert_eq!(written.segments.len(), 2);
+        for (written, segment) in written.segments.iter().zip(&run.segments) {
+            assert_eq!(written.name, segment.name);
+            assert_eq!(written.personal_best, segment.personal_best);
+            assert_eq!(written.best_segment, segment.best_segment);
+        }
+    }
+
+    #[test]
+    fn parses_time_spans() {
+        assert_eq!(parse_time("1.02:03:04.5"), Some(93_784.5));
+        assert_eq!(parse_time("02:03:04"), Some(7_384.0));
+        assert_eq!(parse_time("-00:00:01.25"), Some(-1.25));
+        assert_eq!(parse_time(" 00:00:40 "), Some(40.0));
+        assert_eq!(parse_time("soon"), None);
+    }
+
+    #[test]
+    fn formats_time_spans() {
+        assert_eq!(format_time(3_723.5), "01:02:03.5000000");
+        assert_eq!(format_time(0.0), "00:00:00.0000000");
+        // Negative times don't happen in a run, so they aren't written either.
+        assert_eq!(format_time(-1.0), "00:00:00.0000000");
+        assert_eq!(parse_time(&format_time(57.5)), Some(57.5));
+    }
+
+    #[test]
+    fn finds_self_closing_elements() {
+        let xml = r#"<SplitTime name="Personal Best" /><SplitTime name="Other">x</SplitTime>"#;
+        let found = elements(xml, "SplitTime");
+        assert_eq!(found.len(), 2);
+        assert!(is_personal_best(&found[0]));
+        assert_eq!(found[0].contents, "");
+        assert_eq!(
+            &xml[found[0].span.clone()],
+            r#"<SplitTime name="Personal Best" />"#
+        );
+        assert!(!is_personal_best(&found[1]));
+        assert_eq!(found[1].contents, "x");
+    }
+
+    #[test]
+    fn skips_elements_with_longer_names() {
+        let found = elements(
+            r#"<AttemptCount>3</AttemptCount><Attempt id="1" />"#,
+            "Attempt",
+        );
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].attributes.trim(), r#"id="1""#);
+    }
+}
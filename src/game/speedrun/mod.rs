@@ -0,0 +1,374 @@
+//! An optional speedrun timer that splits automatically whenever a milestone is reached.
+//! The best splits are stored as a LiveSplit `.lss` file, so they can be opened in LiveSplit
+//! or replaced by a splits file edited there.
+
+#[cfg(not(target_family = "wasm"))]
+pub mod livesplit;
+pub mod lss;
+
+use std::{fs, io, path::PathBuf};
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{screen::Screen, ui::prelude::*};
+
+use self::lss::{LssRun, LssSegment, SegmentTime};
+use super::{
+    levels::CurrentLevel,
+    logic::{CurrentCycle, YEARS_PER_CYCLE},
+    records::RunStats,
+    save::{data_dir, DisableSaving},
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SpeedrunTimer>();
+    app.observe(record_split);
+    app.add_systems(
+        OnEnter(Screen::Playing),
+        (load_best_splits, spawn_timer_overlay),
+    );
+    app.add_systems(OnEnter(Screen::Credits), export_splits);
+    #[cfg(not(target_family = "wasm"))]
+    app.add_plugins(livesplit::plugin);
+    app.add_systems(
+        Update,
+        (
+            toggle_timer_overlay.run_if(input_just_pressed(KeyCode::KeyT)),
+            update_timer_overlay,
+        )
+            .chain()
+            .run_if(in_state(Screen::Playing)),
+    );
+}
+
+/// Points in a run at which the timer splits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Milestone {
+    HourglassTaken,
+    SaplingTaken,
+    SaplingPlanted,
+    MonumentFinished,
+    GameFinished,
+}
+
+impl Milestone {
+    /// All milestones, in the order they are listed in the splits.
+    pub const ALL: [Milestone; 5] = [
+        Milestone::HourglassTaken,
+        Milestone::SaplingTaken,
+        Milestone::SaplingPlanted,
+        Milestone::MonumentFinished,
+        Milestone::GameFinished,
+    ];
+
+    /// Segment name in the splits file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Milestone::HourglassTaken => "Hourglass",
+            Milestone::SaplingTaken => "Sapling",
+            Milestone::SaplingPlanted => "Planted",
+            Milestone::MonumentFinished => "Monument",
+            Milestone::GameFinished => "Vault",
+        }
+    }
+}
+
+/// Trigger this event when the player reaches a milestone.
+#[derive(Event)]
+pub struct MilestoneReached(pub Milestone);
+
+/// A split of the current run. Stored in [`RunStats`] so it survives saving and loading.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct Split {
+    pub milestone: Milestone,
+    /// Real time in seconds.
+    pub real_time: f32,
+    pub years: usize,
+}
+
+#[derive(Resource, Default)]
+pub struct SpeedrunTimer {
+    pub visible: bool,
+    /// Splits to compare against, read from the splits file.
+    pub best: Option<LssRun>,
+}
+
+impl SpeedrunTimer {
+    /// Split time of the personal best at the given milestone.
+    pub fn best_split(&self, milestone: Milestone) -> Option<SegmentTime> {
+        self.best
+            .as_ref()?
+            .segments
+            .iter()
+            .find(|segment| segment.name == milestone.name())?
+            .personal_best
+    }
+}
+
+#[derive(Component)]
+struct TimerOverlay;
+
+#[derive(Component)]
+struct TimerText;
+
+/// Splits are kept per level, as their milestones are reached on different islands.
+fn splits_path(level: &str) -> PathBuf {
+    data_dir().join(format!("splits_{level}.lss"))
+}
+
+fn record_split(
+    trigger: Trigger<MilestoneReached>,
+    mut stats: ResMut<RunStats>,
+    current_cycle: Res<CurrentCycle>,
+) {
+    let milestone = trigger.event().0;
+    if stats
+        .splits
+        .iter()
+        .any(|split| split.milestone == milestone)
+    {
+        return;
+    }
+    let split = Split {
+        milestone,
+        real_time: stats.play_time,
+        years: current_cycle.1 * YEARS_PER_CYCLE,
+    };
+    stats.splits.push(split);
+}
+
+fn load_best_splits(mut timer: ResMut<SpeedrunTimer>, current_level: Res<CurrentLevel>) {
+    timer.best = match fs::read_to_string(splits_path(&current_level.0)) {
+        Ok(xml) => LssRun::parse(&xml)
+            .inspect_err(|error| warn!("Ignoring splits file: {error}"))
+            .ok(),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => None,
+        Err(error) => {
+            warn!("Could not read splits file: {error}");
+            None
+        }
+    };
+}
+
+fn spawn_timer_overlay(mut commands: Commands, timer: Res<SpeedrunTimer>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Px(20.0),
+                top: Px(20.0),
+                padding: UiRect::all(Px(10.0)),
+                ..default()
+            },
+            background_color: BackgroundColor(ui_palette::NODE_BACKGROUND),
+            visibility: match timer.visible {
+                true => Visibility::Inherited,
+                false => Visibility::Hidden,
+            },
+            ..default()
+        })
+        .insert((Name::new("Speedrun timer"), StateScoped(Screen::Playing)))
+        .insert(TimerOverlay)
+        .with_children(|overlay| {
+            overlay
+                .spawn(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: ui_palette::LABEL_TEXT,
+                        ..default()
+                    },
+                ))
+                .insert(TimerText);
+        });
+}
+
+fn toggle_timer_overlay(
+    mut timer: ResMut<SpeedrunTimer>,
+    mut overlay: Query<&mut Visibility, With<TimerOverlay>>,
+) {
+    timer.visible = !timer.visible;
+    for mut visibility in &mut overlay {
+        *visibility = match timer.visible {
+            true => Visibility::Inherited,
+            false => Visibility::Hidden,
+        };
+    }
+}
+
+fn update_timer_overlay(
+    timer: Res<SpeedrunTimer>,
+    stats: Res<RunStats>,
+    current_cycle: Res<CurrentCycle>,
+    mut text: Query<&mut Text, With<TimerText>>,
+) {
+    if !timer.visible {
+        return;
+    }
+    let Ok(mut text) = text.get_single_mut() else {
+        return;
+    };
+
+    let mut lines = vec![format!(
+        "{}   {} years",
+        format_timer(stats.play_time),
+        current_cycle.1 * YEARS_PER_CYCLE
+    )];
+    for milestone in Milestone::ALL {
+        let split = stats
+            .splits
+            .iter()
+            .find(|split| split.milestone == milestone);
+        let best = timer.best_split(milestone);
+        let line = match (split, best) {
+            (Some(split), Some(best)) => format!(
+                "{:<10} {:>9} {:>8}",
+                milestone.name(),
+                format_timer(split.real_time),
+                format_delta(split.real_time - best.real_time)
+            ),
+            (Some(split), None) => format!(
+                "{:<10} {:>9}",
+                milestone.name(),
+                format_timer(split.real_time)
+            ),
+            (None, Some(best)) => format!(
+                "{:<10} {:>9}",
+                milestone.name(),
+                format_timer(best.real_time)
+            ),
+            (None, None) => format!("{:<10} {:>9}", milestone.name(), "-"),
+        };
+        lines.push(line);
+    }
+    text.sections[0].value = lines.join("\n");
+}
+
+/// Format seconds as `m:ss.t`, or `h:mm:ss.t` once the run takes longer than an hour.
+fn format_timer(seconds: f32) -> String {
+    let tenths = (seconds.max(0.0) * 10.0) as u64;
+    let (seconds, tenths) = (tenths / 10, tenths % 10);
+    let (hours, minutes, seconds) = (seconds / 3600, seconds % 3600 / 60, seconds % 60);
+    match hours {
+        0 => format!("{minutes}:{seconds:02}.{tenths}"),
+        _ => format!("{hours}:{minutes:02}:{seconds:02}.{tenths}"),
+    }
+}
+
+fn format_delta(seconds: f32) -> String {
+    match seconds < 0.0 {
+        true => format!("-{}", format_timer(-seconds)),
+        false => format!("+{}", format_timer(seconds)),
+    }
+}
+
+/// Count the finished run as an attempt, update the best segments and,
+/// if the run beat the personal best, replace it.
+fn export_splits(
+    stats: Res<RunStats>,
+    mut timer: ResMut<SpeedrunTimer>,
+    current_level: Res<CurrentLevel>,
+    disable_saving: Option<Res<DisableSaving>>,
+) {
+    if disable_saving.is_some() {
+        return;
+    }
+    let mut run = timer.best.clone().unwrap_or_default();
+    run.attempt_count += 1;
+
+    let finish = |splits: &[Option<SegmentTime>]| splits.last().copied().flatten();
+    let splits: Vec<Option<SegmentTime>> = Milestone::ALL
+        .iter()
+        .map(|&milestone| {
+            let split = stats
+                .splits
+                .iter()
+                .find(|split| split.milestone == milestone)?;
+            Some(SegmentTime {
+                real_time: split.real_time,
+                game_time: Some(split.years as f32),
+            })
+        })
+        .collect();
+    let previous_best: Vec<Option<SegmentTime>> = Milestone::ALL
+        .iter()
+        .map(|&milestone| timer.best_split(milestone))
+        .collect();
+
+    // Fewer years is better, real time breaks ties.
+    let rank = |time: SegmentTime| (time.game_time.unwrap_or(f32::MAX), time.real_time);
+    let is_personal_best = match (finish(&splits), finish(&previous_best)) {
+        (Some(new), Some(old)) => rank(new) < rank(old),
+        (Some(_), None) => true,
+        (None, _) => false,
+    };
+
+    // Splits are recorded in the order they were reached.
+    let segment_time = |split: &Split| SegmentTime {
+        real_time: split.real_time,
+        game_time: Some(split.years as f32),
+    };
+    let start = SegmentTime {
+        real_time: 0.0,
+        game_time: Some(0.0),
+    };
+    let segments = Milestone::ALL
+        .iter()
+        .enumerate()
+        .zip(&splits)
+        .map(|((index, &milestone), &split)| {
+            let mut segment = run
+                .segments
+                .iter()
+                .find(|segment| segment.name == milestone.name())
+                .cloned()
+                .unwrap_or_else(|| LssSegment::new(milestone.name()));
+
+            // A segment only counts if the milestone before it in the splits file was the one
+            // reached right before it, otherwise it didn't cover the same part of the route.
+            let reached = stats
+                .splits
+                .iter()
+                .position(|split| split.milestone == milestone);
+            let previous = match reached {
+                Some(0) => Some((None, start)),
+                Some(position) => {
+                    let previous = &stats.splits[position - 1];
+                    Some((Some(previous.milestone), segment_time(previous)))
+                }
+                None => None,
+            };
+            let expected = index.checked_sub(1).map(|index| Milestone::ALL[index]);
+            if let (Some(split), Some((previous_milestone, previous))) = (split, previous) {
+                let duration = SegmentTime {
+                    real_time: split.real_time - previous.real_time,
+                    game_time: split
+                        .game_time
+                        .zip(previous.game_time)
+                        .map(|(split, previous)| split - previous),
+                };
+                let in_order = previous_milestone == expected && duration.real_time >= 0.0;
+                if in_order
+                    && segment
+                        .best_segment
+                        .map_or(true, |best| duration.real_time < best.real_time)
+                {
+                    segment.best_segment = Some(duration);
+                }
+            }
+            if is_personal_best {
+                segment.personal_best = split;
+            }
+            segment
+        })
+        .collect();
+    run.segments = segments;
+
+    let path = splits_path(&current_level.0);
+    let result = fs::create_dir_all(data_dir()).and_then(|()| fs::write(&path, run.to_xml()));
+    if let Err(error) = result {
+        warn!("Could not export splits to {}: {error}", path.display());
+    }
+    timer.best = Some(run);
+}
@@ -0,0 +1,79 @@
+//! The list of all achievements, reachable from the title menu.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use ui_palette::NODE_BACKGROUND;
+
+use super::MenuPage;
+use crate::{
+    game::{
+        achievements::{Achievement, AchievementData},
+        save::format_timestamp,
+    },
+    ui::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(MenuPage::Achievements), enter_achievements);
+    app.add_systems(
+        Update,
+        (
+            handle_achievements_action.run_if(in_state(MenuPage::Achievements)),
+            go_back.run_if(
+                in_state(MenuPage::Achievements).and_then(input_just_pressed(KeyCode::Escape)),
+            ),
+        ),
+    );
+    app.register_type::<AchievementsAction>();
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum AchievementsAction {
+    Back,
+}
+
+fn enter_achievements(mut commands: Commands, data: Res<AchievementData>) {
+    commands
+        .ui_root()
+        .insert(StateScoped(MenuPage::Achievements))
+        .insert(BackgroundColor(NODE_BACKGROUND))
+        .with_children(|children| {
+            children.header(format!(
+                "Achievements ({}/{})",
+                data.unlocked.len(),
+                Achievement::ALL.len()
+            ));
+            for achievement in Achievement::ALL {
+                let status = match (data.unlocked_at(achievement), achievement.goal()) {
+                    (Some(timestamp), _) => format!("Unlocked {}", format_timestamp(timestamp)),
+                    (None, Some((counter, goal))) => {
+                        format!("{}/{goal}", data.counters.get(counter).min(goal))
+                    }
+                    (None, None) => "Locked".into(),
+                };
+                children.label(format!(
+                    "{}: {} ({status})",
+                    achievement.name(),
+                    achievement.description()
+                ));
+            }
+            children.button("Back").insert(AchievementsAction::Back);
+        });
+}
+
+fn handle_achievements_action(
+    mut next_page: ResMut<NextState<MenuPage>>,
+    mut button_query: InteractionQuery<&AchievementsAction>,
+) {
+    for (interaction, action) in &mut button_query {
+        if matches!(interaction, Interaction::Pressed) {
+            match action {
+                AchievementsAction::Back => next_page.set(MenuPage::Title),
+            }
+        }
+    }
+}
+
+fn go_back(mut next_page: ResMut<NextState<MenuPage>>) {
+    next_page.set(MenuPage::Title);
+}
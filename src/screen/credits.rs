@@ -1,16 +1,22 @@
 //! A credits screen that can be accessed from the title screen.
+//! At the end of a run, it is preceded by a summary of the run.
 
 use bevy::prelude::*;
 use ui_palette::NODE_BACKGROUND;
 
 use super::Screen;
 use crate::{
-    game::{assets::SoundtrackKey, audio::soundtrack::PlaySoundtrack, logic::CurrentCycle},
+    game::{
+        assets::SoundtrackKey,
+        audio::soundtrack::PlaySoundtrack,
+        records::{format_duration, record_run, LastRun, TOP_RUNS},
+        save::format_timestamp,
+    },
     ui::prelude::*,
 };
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(OnEnter(Screen::Credits), enter_credits);
+    app.add_systems(OnEnter(Screen::Credits), enter_credits.after(record_run));
     app.add_systems(OnExit(Screen::Credits), exit_credits);
 
     app.add_systems(
@@ -23,17 +29,32 @@ pub(super) fn plugin(app: &mut App) {
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
 #[reflect(Component)]
 enum CreditsAction {
+    ShowCredits,
     Back,
 }
 
-fn enter_credits(mut commands: Commands, current_cycle: Res<CurrentCycle>) {
+#[derive(Component)]
+struct SummaryPage;
+
+#[derive(Component)]
+struct CreditsPage;
+
+fn enter_credits(mut commands: Commands, last_run: Option<Res<LastRun>>) {
+    if let Some(last_run) = last_run {
+        spawn_summary(&mut commands, &last_run);
+    }
+
     commands
         .ui_root()
         .insert(StateScoped(Screen::Credits))
+        .insert(CreditsPage)
         .insert(BackgroundColor(NODE_BACKGROUND))
+        .insert(if last_run.is_some() {
+            Visibility::Hidden
+        } else {
+            Visibility::Inherited
+        })
         .with_children(|children| {
-            children.header(format!("Time taken to complete your project: {} years", current_cycle.1 * 12000));
-
             children.header("Game Design, Programming, 3D Art");
             children.label("Rie");
 
@@ -45,22 +66,86 @@ fn enter_credits(mut commands: Commands, current_cycle: Res<CurrentCycle>) {
 
             children.header("Free sound assets");
             children.label("AudioPaplin, kangaroovindaloo, Andreas Mustola, moogy73, OwlishMedia, Valenspire, juskiddink, dheming");
+
+            children.button("Back to title").insert(CreditsAction::Back);
         });
 
     commands.trigger(PlaySoundtrack::Key(SoundtrackKey::CycleOne));
 }
 
+fn spawn_summary(commands: &mut Commands, last_run: &LastRun) {
+    let record = &last_run.record;
+    commands
+        .ui_root()
+        .insert(StateScoped(Screen::Credits))
+        .insert(SummaryPage)
+        .insert(BackgroundColor(NODE_BACKGROUND))
+        .with_children(|children| {
+            children.header(format!(
+                "Time taken to complete your project: {} years",
+                record.years
+            ));
+
+            if last_run.is_personal_best() {
+                children.header("New personal best!");
+            } else if let Some(best) = &last_run.previous_best {
+                children.label(format!("Personal best: {} years", best.years));
+            }
+            children.label(format!("Real time: {}", format_duration(record.play_time)));
+            children.label(format!("Cycles: {}", record.cycles));
+            children.label(match record.used_hourglass() {
+                true => format!(
+                    "Hourglass used for {}",
+                    format_duration(record.hourglass_time)
+                ),
+                false => "Hourglass not used".into(),
+            });
+
+            children.header("Best runs");
+            let this_run = last_run.records.runs.last();
+            let best_runs = last_run.records.top_runs(&record.level, TOP_RUNS);
+            for (rank, run) in best_runs.into_iter().enumerate() {
+                let marker = match this_run.is_some_and(|this_run| std::ptr::eq(run, this_run)) {
+                    true => " (this run)",
+                    false => "",
+                };
+                children.label(format!(
+                    "{}. {} years in {}, {}{marker}",
+                    rank + 1,
+                    run.years,
+                    format_duration(run.play_time),
+                    format_timestamp(run.completed),
+                ));
+            }
+
+            children
+                .button("Credits")
+                .insert(CreditsAction::ShowCredits);
+        });
+}
+
 fn exit_credits(mut commands: Commands) {
     commands.trigger(PlaySoundtrack::Disable);
+    commands.remove_resource::<LastRun>();
 }
 
 fn handle_credits_action(
     mut next_screen: ResMut<NextState<Screen>>,
     mut button_query: InteractionQuery<&CreditsAction>,
+    mut summary: Query<&mut Visibility, (With<SummaryPage>, Without<CreditsPage>)>,
+    mut credits: Query<&mut Visibility, (With<CreditsPage>, Without<SummaryPage>)>,
 ) {
     for (interaction, action) in &mut button_query {
         if matches!(interaction, Interaction::Pressed) {
             match action {
+                CreditsAction::ShowCredits => {
+                    for mut visibility in &mut summary {
+                        *visibility = Visibility::Hidden;
+                    }
+                    for mut visibility in &mut credits {
+                        *visibility = Visibility::Inherited;
+                    }
+                }
                 CreditsAction::Back => next_screen.set(Screen::Playing),
             }
         }
@@ -0,0 +1,80 @@
+//! The level select of the title menu, shown before picking a slot for a new game.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*};
+use ui_palette::NODE_BACKGROUND;
+
+use super::{slots::SlotPicker, MenuPage};
+use crate::{
+    game::{levels::Levels, records::Records},
+    ui::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(MenuPage::Levels), enter_levels);
+    app.add_systems(
+        Update,
+        (
+            handle_levels_action.run_if(in_state(MenuPage::Levels)),
+            go_back
+                .run_if(in_state(MenuPage::Levels).and_then(input_just_pressed(KeyCode::Escape))),
+        ),
+    );
+    app.register_type::<LevelsAction>();
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum LevelsAction {
+    /// Index of the level in [`Levels`].
+    Pick(usize),
+    Back,
+}
+
+fn enter_levels(mut commands: Commands, levels: Res<Levels>) {
+    let records = Records::load();
+    commands
+        .ui_root()
+        .insert(StateScoped(MenuPage::Levels))
+        .insert(BackgroundColor(NODE_BACKGROUND))
+        .with_children(|children| {
+            children.header("Choose an Island");
+            for (index, level) in levels.0.levels.iter().enumerate() {
+                if levels.is_unlocked(&level.id, &records) {
+                    children
+                        .list_button(level.name.clone())
+                        .insert(LevelsAction::Pick(index));
+                } else {
+                    children.list_button(format!("{} (locked)", level.name));
+                }
+            }
+            children.button("Back").insert(LevelsAction::Back);
+        });
+}
+
+fn handle_levels_action(
+    levels: Res<Levels>,
+    mut slot_picker: ResMut<SlotPicker>,
+    mut next_page: ResMut<NextState<MenuPage>>,
+    mut button_query: InteractionQuery<&LevelsAction>,
+) {
+    for (interaction, action) in &mut button_query {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        match *action {
+            LevelsAction::Pick(index) => {
+                let Some(level) = levels.0.levels.get(index) else {
+                    continue;
+                };
+                slot_picker.level = level.id.clone();
+                slot_picker.previous_page = MenuPage::Levels;
+                next_page.set(MenuPage::Slots);
+            }
+            LevelsAction::Back => next_page.set(MenuPage::Title),
+        }
+    }
+}
+
+fn go_back(mut next_page: ResMut<NextState<MenuPage>>) {
+    next_page.set(MenuPage::Title);
+}
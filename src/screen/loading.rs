@@ -8,7 +8,15 @@ use bevy::{
 
 use super::Screen;
 use crate::{
-    game::assets::{HandleMap, ImageKey, SfxKey, SoundtrackKey},
+    game::{
+        assets::{HandleMap, ImageKey, SfxKey, SoundtrackKey},
+        cycles::CycleDefinitions,
+        data::DataHandle,
+        inventory::ItemDefinitions,
+        levels::LevelDefinitions,
+        progression::ProgressionDefinition,
+        spawn::{interactable::InteractableDefinitions, overlay::LevelOverlay},
+    },
     ui::prelude::*,
 };
 
@@ -69,10 +77,22 @@ fn all_assets_loaded(
     image_handles: Res<HandleMap<ImageKey>>,
     sfx_handles: Res<HandleMap<SfxKey>>,
     soundtrack_handles: Res<HandleMap<SoundtrackKey>>,
+    levels: Res<DataHandle<LevelDefinitions>>,
+    interactables: Res<DataHandle<InteractableDefinitions>>,
+    cycles: Res<DataHandle<CycleDefinitions>>,
+    progression: Res<DataHandle<ProgressionDefinition>>,
+    items: Res<DataHandle<ItemDefinitions>>,
+    overlay: Res<DataHandle<LevelOverlay>>,
 ) -> bool {
     image_handles.all_loaded(&asset_server)
         && sfx_handles.all_loaded(&asset_server)
         && soundtrack_handles.all_loaded(&asset_server)
+        && asset_server.is_loaded_with_dependencies(&levels.0)
+        && asset_server.is_loaded_with_dependencies(&interactables.0)
+        && asset_server.is_loaded_with_dependencies(&cycles.0)
+        && asset_server.is_loaded_with_dependencies(&progression.0)
+        && asset_server.is_loaded_with_dependencies(&items.0)
+        && asset_server.is_loaded_with_dependencies(&overlay.0)
 }
 
 fn continue_to_title(mut next_screen: ResMut<NextState<Screen>>) {
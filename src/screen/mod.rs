@@ -1,17 +1,28 @@
 //! The game's main screen states and transitions between them.
 
+mod achievements;
 mod credits;
+mod levels;
 mod loading;
 mod playing;
+mod slots;
 
 use bevy::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
     app.init_state::<Screen>();
     app.add_sub_state::<PlayState>();
+    app.add_sub_state::<MenuPage>();
     app.enable_state_scoped_entities::<Screen>();
 
-    app.add_plugins((loading::plugin, credits::plugin, playing::plugin));
+    app.add_plugins((
+        loading::plugin,
+        credits::plugin,
+        playing::plugin,
+        slots::plugin,
+        levels::plugin,
+        achievements::plugin,
+    ));
 }
 
 /// The game's main screen states.
@@ -30,3 +41,15 @@ pub enum PlayState {
     InMenu,
     InGame,
 }
+
+/// The pages of the menu shown while the game is paused.
+#[derive(SubStates, Debug, Hash, PartialEq, Eq, Clone, Default)]
+#[source(PlayState = PlayState::InMenu)]
+pub enum MenuPage {
+    #[default]
+    Title,
+    Levels,
+    Slots,
+    Confirm,
+    Achievements,
+}
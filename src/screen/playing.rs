@@ -7,49 +7,82 @@ use bevy::{
     window::{CursorGrabMode, PrimaryWindow},
 };
 
-use super::{PlayState, Screen};
+use super::{
+    slots::{SlotPicker, SlotPickerMode},
+    MenuPage, PlayState, Screen,
+};
 use crate::game::{
-    assets::SoundtrackKey, audio::soundtrack::PlaySoundtrack, logic::PromptText,
+    assets::SoundtrackKey,
+    audio::soundtrack::PlaySoundtrack,
+    input::replaying,
+    levels::Levels,
+    logic::PromptText,
+    save::{load_game, read_slot, ActiveSlot, SaveGame, SAVE_SLOTS},
     spawn::level::SpawnLevel,
 };
 use crate::ui::prelude::*;
 
 pub(super) fn plugin(app: &mut App) {
-    app.add_systems(OnEnter(Screen::Playing), enter_playing);
+    app.init_resource::<RunStarted>();
+    app.add_systems(OnEnter(Screen::Playing), enter_playing.after(load_game));
     app.add_systems(OnExit(Screen::Playing), exit_playing);
-    app.add_systems(OnEnter(PlayState::InMenu), enter_menu);
+    app.add_systems(OnEnter(PlayState::InMenu), release_cursor);
     app.add_systems(OnExit(PlayState::InMenu), exit_menu);
+    app.add_systems(OnEnter(PlayState::InGame), start_run);
+    app.add_systems(OnEnter(MenuPage::Title), enter_menu);
     app.enable_state_scoped_entities::<PlayState>();
+    app.enable_state_scoped_entities::<MenuPage>();
 
     app.add_systems(
         Update,
-        toggle_pause
-            .run_if(in_state(Screen::Playing).and_then(input_just_pressed(KeyCode::Escape))),
+        toggle_pause.run_if(
+            in_state(Screen::Playing)
+                .and_then(input_just_pressed(KeyCode::Escape))
+                .and_then(in_state(PlayState::InGame).or_else(in_state(MenuPage::Title)))
+                // A replay pauses exactly when the recorded run did.
+                .and_then(not(replaying)),
+        ),
     );
     app.add_systems(
         Update,
-        handle_menu_action.run_if(in_state(PlayState::InMenu)),
+        handle_menu_action.run_if(in_state(MenuPage::Title).and_then(not(replaying))),
     );
     app.add_systems(Update, capture_cursor.run_if(in_state(PlayState::InGame)));
 }
 
+/// Whether the player has left the title menu since the world was loaded.
+#[derive(Resource, Default)]
+pub(super) struct RunStarted(pub bool);
+
+/// Insert this resource before reloading the world to skip the title menu.
+#[derive(Resource)]
+pub(super) struct StartImmediately;
+
 #[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
 #[reflect(Component)]
 enum TitleAction {
-    Play,
+    Continue,
+    NewGame,
+    LoadGame,
+    Achievements,
     Credits,
     /// Exit doesn't work well with embedded applications.
     #[cfg(not(target_family = "wasm"))]
     Exit,
 }
 
-fn enter_menu(mut commands: Commands, mut windows: Query<&mut Window, With<PrimaryWindow>>) {
+fn release_cursor(mut windows: Query<&mut Window, With<PrimaryWindow>>) {
     let mut primary_window = windows.single_mut();
     primary_window.cursor.grab_mode = CursorGrabMode::None;
     primary_window.cursor.visible = true;
+}
+
+fn enter_menu(mut commands: Commands, run_started: Res<RunStarted>, active_slot: Res<ActiveSlot>) {
+    let can_continue = run_started.0 || read_slot(active_slot.0).is_some();
+    let any_saves = (0..SAVE_SLOTS).any(|slot| read_slot(slot).is_some());
     commands
         .ui_root()
-        .insert(StateScoped(PlayState::InMenu))
+        .insert(StateScoped(MenuPage::Title))
         .with_children(|root| {
             root.spawn(NodeBundle {
                 style: Style {
@@ -68,7 +101,16 @@ fn enter_menu(mut commands: Commands, mut windows: Query<&mut Window, With<Prima
                 ..default()
             })
             .with_children(|container| {
-                container.button("Play").insert(TitleAction::Play);
+                if can_continue {
+                    container.button("Continue").insert(TitleAction::Continue);
+                }
+                container.button("New Game").insert(TitleAction::NewGame);
+                if any_saves {
+                    container.button("Load Game").insert(TitleAction::LoadGame);
+                }
+                container
+                    .button("Achievements")
+                    .insert(TitleAction::Achievements);
                 container.button("Credits").insert(TitleAction::Credits);
 
                 #[cfg(not(target_family = "wasm"))]
@@ -98,10 +140,21 @@ fn capture_cursor(
     }
 }
 
-fn enter_playing(mut commands: Commands, mut windows: Query<&mut Window, With<PrimaryWindow>>) {
+fn enter_playing(
+    mut commands: Commands,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut run_started: ResMut<RunStarted>,
+    start_immediately: Option<Res<StartImmediately>>,
+    mut next_state: ResMut<NextState<PlayState>>,
+) {
+    run_started.0 = false;
+    if start_immediately.is_some() {
+        commands.remove_resource::<StartImmediately>();
+        next_state.set(PlayState::InGame);
+    }
+
     commands.trigger(SpawnLevel);
     commands.trigger(PlaySoundtrack::Key(SoundtrackKey::OceanAmbiance));
-    commands.trigger(PlaySoundtrack::Key(SoundtrackKey::CycleOne));
 
     // Grab cursor
     let mut primary_window = windows.single_mut();
@@ -164,9 +217,20 @@ fn toggle_pause(
     });
 }
 
+/// The first time the game is entered, make sure the run shows up in its save slot.
+fn start_run(mut commands: Commands, mut run_started: ResMut<RunStarted>) {
+    if !run_started.0 {
+        run_started.0 = true;
+        commands.trigger(SaveGame);
+    }
+}
+
 fn handle_menu_action(
     input: Res<ButtonInput<KeyCode>>,
     mut next_screen: ResMut<NextState<PlayState>>,
+    mut next_page: ResMut<NextState<MenuPage>>,
+    mut slot_picker: ResMut<SlotPicker>,
+    levels: Res<Levels>,
     mut button_query: InteractionQuery<&TitleAction>,
     #[cfg(not(target_family = "wasm"))] mut app_exit: EventWriter<AppExit>,
 ) {
@@ -178,7 +242,25 @@ fn handle_menu_action(
     for (interaction, action) in &mut button_query {
         if matches!(interaction, Interaction::Pressed) {
             match action {
-                TitleAction::Play => next_screen.set(PlayState::InGame),
+                TitleAction::Continue => next_screen.set(PlayState::InGame),
+                TitleAction::NewGame => {
+                    slot_picker.mode = SlotPickerMode::NewGame;
+                    slot_picker.previous_page = MenuPage::Title;
+                    // With a single level, there is nothing to choose from.
+                    match levels.0.levels.as_slice() {
+                        [level] => {
+                            slot_picker.level = level.id.clone();
+                            next_page.set(MenuPage::Slots);
+                        }
+                        _ => next_page.set(MenuPage::Levels),
+                    }
+                }
+                TitleAction::LoadGame => {
+                    slot_picker.mode = SlotPickerMode::LoadGame;
+                    slot_picker.previous_page = MenuPage::Title;
+                    next_page.set(MenuPage::Slots);
+                }
+                TitleAction::Achievements => next_page.set(MenuPage::Achievements),
                 #[cfg(not(target_family = "wasm"))]
                 TitleAction::Exit => {
                     app_exit.send(AppExit::Success);
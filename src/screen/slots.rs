@@ -0,0 +1,279 @@
+//! The save slot picker of the title menu and the confirmation dialog
+//! shown before a save is deleted or overwritten.
+
+use bevy::{input::common_conditions::input_just_pressed, prelude::*, ui::Val::*};
+use ui_palette::NODE_BACKGROUND;
+
+use super::{
+    playing::{RunStarted, StartImmediately},
+    MenuPage, Screen,
+};
+use crate::{
+    game::{
+        levels::{CurrentLevel, Levels, FIRST_LEVEL},
+        save::{delete_slot, format_timestamp, read_slot, ActiveSlot, SAVE_SLOTS},
+    },
+    ui::prelude::*,
+};
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<SlotPicker>();
+    app.add_systems(OnEnter(MenuPage::Slots), enter_slots);
+    app.add_systems(OnEnter(MenuPage::Confirm), enter_confirm);
+
+    app.add_systems(
+        Update,
+        (
+            handle_slot_action.run_if(in_state(MenuPage::Slots)),
+            handle_confirm_action.run_if(in_state(MenuPage::Confirm)),
+            go_back.run_if(
+                in_state(MenuPage::Slots)
+                    .or_else(in_state(MenuPage::Confirm))
+                    .and_then(input_just_pressed(KeyCode::Escape)),
+            ),
+        ),
+    );
+    app.register_type::<SlotAction>();
+    app.register_type::<ConfirmAction>();
+}
+
+/// What picking a slot does.
+#[derive(Resource)]
+pub(super) struct SlotPicker {
+    pub mode: SlotPickerMode,
+    /// The level a new game is started on.
+    pub level: String,
+    /// Where going back from the slots leads.
+    pub previous_page: MenuPage,
+    confirmation: Option<Confirmation>,
+}
+
+impl Default for SlotPicker {
+    fn default() -> Self {
+        Self {
+            mode: default(),
+            level: FIRST_LEVEL.into(),
+            previous_page: MenuPage::Title,
+            confirmation: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(super) enum SlotPickerMode {
+    #[default]
+    NewGame,
+    LoadGame,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Confirmation {
+    Overwrite(usize),
+    Delete(usize),
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum SlotAction {
+    Pick(usize),
+    Delete(usize),
+    Back,
+}
+
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+enum ConfirmAction {
+    Yes,
+    No,
+}
+
+fn enter_slots(mut commands: Commands, slot_picker: Res<SlotPicker>, levels: Res<Levels>) {
+    commands
+        .ui_root()
+        .insert(StateScoped(MenuPage::Slots))
+        .insert(BackgroundColor(NODE_BACKGROUND))
+        .with_children(|children| {
+            children.header(match slot_picker.mode {
+                SlotPickerMode::NewGame => "New Game",
+                SlotPickerMode::LoadGame => "Load Game",
+            });
+
+            for slot in 0..SAVE_SLOTS {
+                let save = read_slot(slot);
+                children
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Row,
+                            column_gap: Px(10.0),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|row| {
+                        let Some(save) = save else {
+                            let mut entry = row.list_button(format!("Slot {}: Empty", slot + 1));
+                            // There is nothing to load from an empty slot.
+                            if slot_picker.mode == SlotPickerMode::NewGame {
+                                entry.insert(SlotAction::Pick(slot));
+                            }
+                            return;
+                        };
+                        row.list_button(format!(
+                            "Slot {}: {}, {} cycles, {} years\nLast played {}",
+                            slot + 1,
+                            levels.name(&save.level),
+                            save.cycles_elapsed,
+                            save.years(),
+                            format_timestamp(save.last_played),
+                        ))
+                        .insert(SlotAction::Pick(slot));
+                        row.button("Delete").insert(SlotAction::Delete(slot));
+                    });
+            }
+
+            children.button("Back").insert(SlotAction::Back);
+        });
+}
+
+fn enter_confirm(mut commands: Commands, slot_picker: Res<SlotPicker>) {
+    let Some(confirmation) = slot_picker.confirmation else {
+        return;
+    };
+    commands
+        .ui_root()
+        .insert(StateScoped(MenuPage::Confirm))
+        .insert(BackgroundColor(NODE_BACKGROUND))
+        .with_children(|children| {
+            children.header(match confirmation {
+                Confirmation::Overwrite(slot) => {
+                    format!("Overwrite the save in slot {}?", slot + 1)
+                }
+                Confirmation::Delete(slot) => format!("Delete the save in slot {}?", slot + 1),
+            });
+            children
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        column_gap: Px(10.0),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|row| {
+                    row.button("Yes").insert(ConfirmAction::Yes);
+                    row.button("No").insert(ConfirmAction::No);
+                });
+        });
+}
+
+fn handle_slot_action(
+    mut commands: Commands,
+    mut slot_picker: ResMut<SlotPicker>,
+    mut active_slot: ResMut<ActiveSlot>,
+    mut current_level: ResMut<CurrentLevel>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut next_page: ResMut<NextState<MenuPage>>,
+    mut button_query: InteractionQuery<&SlotAction>,
+) {
+    for (interaction, action) in &mut button_query {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        match *action {
+            SlotAction::Pick(slot) => {
+                if slot_picker.mode == SlotPickerMode::NewGame && read_slot(slot).is_some() {
+                    slot_picker.confirmation = Some(Confirmation::Overwrite(slot));
+                    next_page.set(MenuPage::Confirm);
+                } else {
+                    start_slot(
+                        slot,
+                        &slot_picker,
+                        &mut commands,
+                        &mut active_slot,
+                        &mut current_level,
+                        &mut next_screen,
+                    );
+                }
+            }
+            SlotAction::Delete(slot) => {
+                slot_picker.confirmation = Some(Confirmation::Delete(slot));
+                next_page.set(MenuPage::Confirm);
+            }
+            SlotAction::Back => next_page.set(slot_picker.previous_page.clone()),
+        }
+    }
+}
+
+fn handle_confirm_action(
+    mut commands: Commands,
+    mut slot_picker: ResMut<SlotPicker>,
+    mut active_slot: ResMut<ActiveSlot>,
+    mut current_level: ResMut<CurrentLevel>,
+    run_started: Res<RunStarted>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    mut next_page: ResMut<NextState<MenuPage>>,
+    mut button_query: InteractionQuery<&ConfirmAction>,
+) {
+    for (interaction, action) in &mut button_query {
+        if !matches!(interaction, Interaction::Pressed) {
+            continue;
+        }
+        let confirmation = slot_picker.confirmation.take();
+        match (action, confirmation) {
+            (ConfirmAction::Yes, Some(Confirmation::Overwrite(slot))) => {
+                delete_slot(slot);
+                start_slot(
+                    slot,
+                    &slot_picker,
+                    &mut commands,
+                    &mut active_slot,
+                    &mut current_level,
+                    &mut next_screen,
+                );
+            }
+            (ConfirmAction::Yes, Some(Confirmation::Delete(slot))) => {
+                delete_slot(slot);
+                if slot == active_slot.0 && !run_started.0 {
+                    // The world in the background was loaded from this save, start over.
+                    next_screen.set(Screen::Loading);
+                } else {
+                    next_page.set(MenuPage::Slots);
+                }
+            }
+            _ => next_page.set(MenuPage::Slots),
+        }
+    }
+}
+
+fn go_back(
+    current_page: Res<State<MenuPage>>,
+    slot_picker: Res<SlotPicker>,
+    mut next_page: ResMut<NextState<MenuPage>>,
+) {
+    next_page.set(match current_page.get() {
+        MenuPage::Confirm => MenuPage::Slots,
+        _ => slot_picker.previous_page.clone(),
+    });
+}
+
+/// Make `slot` the active one and reload the world from it, skipping the title menu.
+/// A new game starts on the picked level, a loaded one on the level it was saved on.
+fn start_slot(
+    slot: usize,
+    slot_picker: &SlotPicker,
+    commands: &mut Commands,
+    active_slot: &mut ActiveSlot,
+    current_level: &mut ResMut<CurrentLevel>,
+    next_screen: &mut NextState<Screen>,
+) {
+    let level = match slot_picker.mode {
+        SlotPickerMode::NewGame => None,
+        SlotPickerMode::LoadGame => read_slot(slot).map(|save| save.level),
+    };
+    current_level.set_if_neq(CurrentLevel(
+        level.unwrap_or_else(|| slot_picker.level.clone()),
+    ));
+    active_slot.0 = slot;
+    commands.insert_resource(StartImmediately);
+    next_screen.set(Screen::Loading);
+}
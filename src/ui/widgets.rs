@@ -9,6 +9,9 @@ pub trait Widgets {
     /// Spawn a simple button with text.
     fn button(&mut self, text: impl Into<String>) -> EntityCommands;
 
+    /// Spawn a wide button with smaller text. Used for entries in a list.
+    fn list_button(&mut self, text: impl Into<String>) -> EntityCommands;
+
     /// Spawn a simple header label. Bigger than [`Widgets::label`].
     fn header(&mut self, text: impl Into<String>) -> EntityCommands;
 
@@ -24,8 +27,6 @@ impl<T: Spawn> Widgets for T {
                 style: Style {
                     width: Px(250.0),
                     height: Px(65.0),
-
-                    // border: UiRect::all(Px(2.0)),
                     justify_content: JustifyContent::Center,
                     align_items: AlignItems::Center,
                     ..default()
@@ -57,6 +58,43 @@ impl<T: Spawn> Widgets for T {
         entity
     }
 
+    fn list_button(&mut self, text: impl Into<String>) -> EntityCommands {
+        let mut entity = self.spawn((
+            Name::new("List Button"),
+            ButtonBundle {
+                style: Style {
+                    width: Px(800.0),
+                    height: Px(65.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                border_radius: BorderRadius::all(Px(8.0)),
+                border_color: BorderColor(BUTTON_HOVERED_BACKGROUND),
+                ..default()
+            },
+            InteractionPalette {
+                none: NODE_BACKGROUND,
+                hovered: BUTTON_HOVERED_BACKGROUND,
+                pressed: BUTTON_PRESSED_BACKGROUND,
+            },
+        ));
+        entity.with_children(|children| {
+            children.spawn((
+                Name::new("Button Text"),
+                TextBundle::from_section(
+                    text,
+                    TextStyle {
+                        font_size: 26.0,
+                        color: BUTTON_TEXT,
+                        ..default()
+                    },
+                ),
+            ));
+        });
+        entity
+    }
+
     fn header(&mut self, text: impl Into<String>) -> EntityCommands {
         let mut entity = self.spawn((
             Name::new("Header"),